@@ -1,14 +1,299 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::BufRead;
+use std::str::FromStr;
+
+#[cfg(feature = "alloc-stats")]
+pub mod alloc_stats;
+pub mod repl;
+
+pub use repl::Repl;
+
+pub fn fail(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
+
+pub struct Args {
+    positional: Vec<String>,
+    flags: Vec<String>,
+    params: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn from_env(param_names: &[&str]) -> Self {
+        let mut positional = Vec::new();
+        let mut flags = Vec::new();
+        let mut params = HashMap::new();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg != "--param" {
+                match arg.strip_prefix("--") {
+                    Some(flag) => flags.push(flag.to_owned()),
+                    None => positional.push(arg),
+                }
+                continue;
+            }
+            let Some(param) = args.next() else {
+                fail("Expected name=value after --param");
+            };
+            let Some((name, value)) = param.split_once('=') else {
+                fail(format!("Expected name=value after --param, got '{}'", param));
+            };
+            if !param_names.contains(&name) {
+                fail(format!("Unknown parameter '{}' (known parameters: {:?})", name, param_names));
+            }
+            params.insert(name.to_owned(), value.to_owned());
+        }
+        Self { positional, flags, params }
+    }
+
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    pub fn positional_or<T: FromStr>(&self, index: usize, default: T) -> T where T::Err: Display {
+        match self.positional(index) {
+            Some(arg) => arg.parse().unwrap_or_else(|e| {
+                fail(format!("Invalid argument '{}': {}", arg, e))
+            }),
+            None => default,
+        }
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    pub fn param<T: FromStr>(&self, name: &str, default: T) -> T where T::Err: Display {
+        match self.params.get(name) {
+            Some(value) => value.parse().unwrap_or_else(|e| {
+                fail(format!("Invalid value '{}' for parameter '{}': {}", value, name, e))
+            }),
+            None => default,
+        }
+    }
+}
+
+static ARGS: std::sync::OnceLock<Args> = std::sync::OnceLock::new();
+
+pub fn init_args(param_names: &[&str]) -> &'static Args {
+    ARGS.get_or_init(|| Args::from_env(param_names))
+}
+
+// lets parts check flags such as --explain
+pub fn args() -> &'static Args {
+    ARGS.get().expect("arguments are parsed before solving")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, line: None, column: None, message: message.into() }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, line: None, column: None, message: message.into() }
+    }
+
+    // lines and columns are zero-based, as from enumerate(), and shown one-based
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning")?,
+            Severity::Error => write!(f, "error")?,
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, " at {}:{}", line + 1, column + 1)?,
+            (Some(line), None) => write!(f, " on line {}", line + 1)?,
+            _ => {}
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// the shared checks for a rectangular grid of tiles
+pub fn check_grid(grid: &str, is_tile: impl Fn(char) -> bool) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let width = grid.lines().next().map_or(0, |l| l.chars().count());
+    if width == 0 {
+        diagnostics.push(Diagnostic::error("empty grid"));
+    }
+    for (y, line) in grid.lines().enumerate() {
+        let len = line.chars().count();
+        if len != width {
+            let message = format!("row has width {}, expected {}", len, width);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+        for (x, c) in line.chars().enumerate() {
+            if !is_tile(c) {
+                diagnostics.push(Diagnostic::error(format!("unknown tile '{}'", c)).at(y, x));
+            }
+        }
+    }
+    diagnostics
+}
+
+pub fn check(validate: impl FnOnce(&str) -> Vec<Diagnostic>) {
+    let input: String = stdin();
+    let diagnostics = validate(&input);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    if errors > 0 {
+        fail(format!("{} error(s), {} warning(s)", errors, diagnostics.len() - errors));
+    }
+    println!("input is valid ({} warning(s))", diagnostics.len());
+}
+
+pub type Command = (&'static str, fn(&Args));
+
+pub fn command(args: &Args, name: &str, commands: &[Command]) {
+    match commands.iter().find(|&&(n, _)| n == name) {
+        Some((_, command)) => command(args),
+        None => {
+            let names = commands.iter().map(|&(n, _)| n).collect::<Vec<_>>();
+            fail(format!("Expected AoC part (1 or 2) or command {:?}, got '{}'", names, name))
+        }
+    }
+}
+
+pub trait Input {
+    fn read(reader: Box<dyn BufRead>) -> Self;
+}
+
+impl Input for String {
+    fn read(reader: Box<dyn BufRead>) -> Self {
+        std::io::read_to_string(reader).expect("failed to read input from stdin")
+    }
+}
+
+pub struct Lines {
+    reader: Box<dyn BufRead>,
+}
+
+impl Input for Lines {
+    fn read(reader: Box<dyn BufRead>) -> Self {
+        Self { reader }
+    }
+}
+
+impl Lines {
+    // reuses a single line buffer, so memory use doesn't grow with the input
+    pub fn fold<T>(mut self, init: T, mut f: impl FnMut(T, &str) -> T) -> T {
+        let mut acc = init;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = self.reader
+                .read_line(&mut line)
+                .expect("failed to read input from stdin");
+            if read == 0 {
+                return acc;
+            }
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            acc = f(acc, line);
+        }
+    }
+}
+
+pub fn stdin<T: Input>() -> T {
+    T::read(Box::new(std::io::stdin().lock()))
+}
+
+pub fn solve<T: Display>(part: impl FnOnce() -> T) {
+    #[cfg(feature = "alloc-stats")]
+    alloc_stats::reset();
+    let answer = part();
+    #[cfg(feature = "alloc-stats")]
+    let stats = alloc_stats::stats();
+    println!("{}", answer);
+    #[cfg(feature = "alloc-stats")]
+    eprintln!("{}", stats);
+}
+
 #[macro_export]
 macro_rules! main {
-    () => {
+    (@params { $($name:ident: $ty:ty = $default:expr),* $(,)? }) => {
+        struct Params {
+            $($name: $ty,)*
+        }
+
+        static PARAMS: std::sync::OnceLock<Params> = std::sync::OnceLock::new();
+
+        fn params() -> &'static Params {
+            PARAMS.get().expect("parameters are set before solving")
+        }
+    };
+    (@param_names) => { &[] };
+    (@param_names { $($name:ident: $ty:ty = $default:expr),* $(,)? }) => {
+        &[$(stringify!($name)),*]
+    };
+    (@init_params $args:ident { $($name:ident: $ty:ty = $default:expr),* $(,)? }) => {
+        let _ = PARAMS.set(Params {
+            $($name: $args.param(stringify!($name), $default),)*
+        });
+    };
+    (@check) => { $crate::fail("This day has no input validation") };
+    (@check $validate:path) => { $crate::check($validate) };
+    (@repl $args:ident) => { $crate::fail("This day has no REPL") };
+    (@repl $args:ident $repl:path) => {{
+        let input = $crate::repl::read_input($args.positional(1));
+        $repl(&input).run()
+    }};
+    (@commands) => { &[] };
+    (@commands { $($name:ident: $command:path),* $(,)? }) => {
+        &[$((stringify!($name), $command as fn(&$crate::Args)) as $crate::Command),*]
+    };
+    (
+        $(params $params:tt)?
+        $(check: $validate:path,)?
+        $(repl: $repl:path,)?
+        $(commands $commands:tt)?
+    ) => {
+        $($crate::main!(@params $params);)?
+
         fn main() {
-            let input = std::io::read_to_string(std::io::stdin())
-                .expect("failed to read input from stdin");
-            match std::env::args().nth(1).as_deref() {
-                Some("1") => println!("{}", part_1(input)),
-                Some("2") => println!("{}", part_2(input)),
-                _ => eprintln!("Expected AoC part as argument (1 or 2)")
+            let args = $crate::init_args($crate::main!(@param_names $($params)?));
+            $($crate::main!(@init_params args $params);)?
+            if args.flag("check") {
+                $crate::main!(@check $($validate)?)
+            } else {
+                match args.positional(0) {
+                    Some("1") => $crate::solve(|| part_1($crate::stdin())),
+                    Some("2") => $crate::solve(|| part_2($crate::stdin())),
+                    Some("repl") => $crate::main!(@repl args $($repl)?),
+                    Some(name) => $crate::command(args, name, $crate::main!(@commands $($commands)?)),
+                    None => eprintln!("Expected AoC part as argument (1 or 2)")
+                }
             }
         }
-    }
+    };
 }
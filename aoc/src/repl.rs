@@ -0,0 +1,109 @@
+use std::fmt::Display;
+use std::io::Write;
+use std::str::FromStr;
+
+type Run<'s, S> = Box<dyn Fn(&mut S, &[&str]) -> Result<(), String> + 's>;
+
+struct Command<'s, S> {
+    name: &'static str,
+    usage: &'static str,
+    run: Run<'s, S>,
+}
+
+impl<S> Command<'_, S> {
+    fn usage(&self) -> String {
+        match self.usage {
+            "" => self.name.to_owned(),
+            usage => format!("{} {}", self.name, usage),
+        }
+    }
+}
+
+pub struct Repl<'s, S> {
+    input: &'s str,
+    init: Box<dyn Fn(&'s str) -> S + 's>,
+    commands: Vec<Command<'s, S>>,
+}
+
+impl<'s, S> Repl<'s, S> {
+    pub fn new(input: &'s str, init: impl Fn(&'s str) -> S + 's) -> Self {
+        Self { input, init: Box::new(init), commands: Vec::new() }
+    }
+
+    pub fn command(
+        mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: impl Fn(&mut S, &[&str]) -> Result<(), String> + 's,
+    ) -> Self {
+        self.commands.push(Command { name, usage, run: Box::new(run) });
+        self
+    }
+
+    fn help(&self) {
+        for command in &self.commands {
+            println!("{}", command.usage());
+        }
+        println!("reset");
+        println!("help");
+        println!("quit");
+    }
+
+    pub fn run(self) {
+        let mut state = (self.init)(self.input);
+        let mut line = String::new();
+        loop {
+            print!("> ");
+            std::io::stdout().flush().expect("failed to flush stdout");
+            line.clear();
+            let read = std::io::stdin()
+                .read_line(&mut line)
+                .expect("failed to read command from stdin");
+            if read == 0 {
+                println!();
+                return;
+            }
+
+            let words = line.split_ascii_whitespace().collect::<Vec<_>>();
+            let Some((&name, args)) = words.split_first() else {
+                continue;
+            };
+            match name {
+                "reset" => state = (self.init)(self.input),
+                "help" => self.help(),
+                "quit" | "exit" => return,
+                _ => match self.commands.iter().find(|c| c.name == name) {
+                    Some(command) => {
+                        if let Err(e) = (command.run)(&mut state, args) {
+                            println!("error: {}", e);
+                            println!("usage: {}", command.usage());
+                        }
+                    }
+                    None => println!("unknown command '{}' (try 'help')", name),
+                }
+            }
+        }
+    }
+}
+
+pub fn arg<T: FromStr>(args: &[&str], index: usize) -> Result<T, String> where T::Err: Display {
+    let arg = args.get(index).ok_or_else(|| format!("missing argument {}", index + 1))?;
+    arg.parse().map_err(|e| format!("invalid argument '{}': {}", arg, e))
+}
+
+pub fn arg_or<T: FromStr>(args: &[&str], index: usize, default: T) -> Result<T, String> where T::Err: Display {
+    match args.get(index) {
+        Some(_) => arg(args, index),
+        None => Ok(default),
+    }
+}
+
+pub fn read_input(path: Option<&str>) -> String {
+    // stdin is taken by the REPL itself, so the puzzle input comes from a file
+    let Some(path) = path else {
+        crate::fail("Expected puzzle input file after 'repl'");
+    };
+    std::fs::read_to_string(path).unwrap_or_else(|e| {
+        crate::fail(format!("Failed to read '{}': {}", path, e))
+    })
+}
@@ -1,37 +1,484 @@
-fn part_1(input: String) -> u32 {
-    let mut sum = 0;
-    for line in input.lines() {
-        sum += line.chars().find_map(|c| c.to_digit(10)).unwrap() * 10;
-        sum += line.chars().rev().find_map(|c| c.to_digit(10)).unwrap();
+use std::str::FromStr;
+use std::time::Instant;
+use aoc::{Args, Diagnostic, Lines};
+
+const PATTERNS: [(&str, u32); 18] = [
+    ("1", 1), ("one", 1),
+    ("2", 2), ("two", 2),
+    ("3", 3), ("three", 3),
+    ("4", 4), ("four", 4),
+    ("5", 5), ("five", 5),
+    ("6", 6), ("six", 6),
+    ("7", 7), ("seven", 7),
+    ("8", 8), ("eight", 8),
+    ("9", 9), ("nine", 9),
+];
+
+const ENGLISH: [(&str, u32); 9] = [
+    ("one", 1), ("two", 2), ("three", 3), ("four", 4), ("five", 5),
+    ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9),
+];
+
+const GERMAN: [(&str, u32); 10] = [
+    ("null", 0), ("eins", 1), ("zwei", 2), ("drei", 3), ("vier", 4),
+    ("fünf", 5), ("sechs", 6), ("sieben", 7), ("acht", 8), ("neun", 9),
+];
+
+const FRENCH: [(&str, u32); 10] = [
+    ("zéro", 0), ("un", 1), ("deux", 2), ("trois", 3), ("quatre", 4),
+    ("cinq", 5), ("six", 6), ("sept", 7), ("huit", 8), ("neuf", 9),
+];
+
+const TEENS: [&str; 10] = [
+    "ten", "eleven", "twelve", "thirteen", "fourteen",
+    "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+
+const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+// "zero" through "ninety-nine", including hyphenated compounds
+fn english_numbers() -> Vec<(String, u32)> {
+    let mut words = vec![("zero".to_owned(), 0)];
+    words.extend(ENGLISH.iter().map(|&(w, n)| (w.to_owned(), n)));
+    words.extend(TEENS.iter().zip(10..).map(|(&w, n)| (w.to_owned(), n)));
+    for (tens, t) in TENS.iter().zip((20..).step_by(10)) {
+        words.push((tens.to_string(), t));
+        for &(ones, n) in &ENGLISH {
+            words.push((format!("{}-{}", tens, ones), t + n));
+        }
     }
-    sum
+    words
+}
+
+fn digits() -> Vec<(String, u32)> {
+    (0..10).map(|n| (n.to_string(), n)).collect()
+}
+
+#[derive(Debug, Clone)]
+struct Vocabulary(Vec<(String, u32)>);
+
+impl Vocabulary {
+    fn new(words: Vec<(String, u32)>) -> Result<Self, String> {
+        for (i, (word, value)) in words.iter().enumerate() {
+            // an empty word would match at every byte
+            if word.is_empty() {
+                return Err(format!("empty word for value {}", value));
+            }
+            if let Some((_, other)) = words[..i].iter().find(|(w, v)| w == word && v != value) {
+                return Err(format!("'{}' is both {} and {}", word, other, value));
+            }
+        }
+        Ok(Self(words))
+    }
+
+    fn parse_words(contents: &str) -> Result<Vec<(String, u32)>, String> {
+        contents.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|line| {
+                let (word, value) = line.rsplit_once(' ')
+                    .ok_or_else(|| format!("expected 'word value', got '{}'", line))?;
+                let value = value.parse()
+                    .map_err(|e| format!("invalid value in '{}': {}", line, e))?;
+                Ok((word.trim().to_owned(), value))
+            })
+            .collect()
+    }
+
+    fn load(path: &str) -> Result<Vec<(String, u32)>, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read vocabulary '{}': {}", path, e))?;
+        Self::parse_words(&contents).map_err(|e| format!("in vocabulary '{}': {}", path, e))
+    }
+
+    fn decoder(&self) -> Decoder {
+        Decoder::new(self.0.iter().map(|(w, n)| (w.as_str(), *n)))
+    }
+}
+
+impl FromStr for Vocabulary {
+    type Err = String;
+
+    // a comma separated list of built-in vocabularies and vocabulary files
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the original part 2 table has no '0', so only 1-9 count as digits here
+        let mut words = digits()[1..].to_vec();
+        for name in s.split(',') {
+            let builtin = match name {
+                "english" => &ENGLISH[..],
+                "german" => &GERMAN[..],
+                "french" => &FRENCH[..],
+                "numbers" => {
+                    words.extend(english_numbers());
+                    continue;
+                }
+                path => {
+                    words.extend(Self::load(path)?);
+                    continue;
+                }
+            };
+            words.extend(builtin.iter().map(|&(w, n)| (w.to_owned(), n)));
+        }
+        Self::new(words)
+    }
+}
+
+// Aho-Corasick automaton with the failure links folded into a full transition table,
+// so scanning is a single table lookup per byte.
+struct Automaton {
+    transitions: Vec<[u32; 256]>,
+    matches: Vec<Vec<(usize, u32)>>,
+    max_len: usize,
 }
 
-fn part_2(input: String) -> u32 {
-    let mut sum = 0;
-    for line in input.lines() {
-        let patterns = [
-            ("1", 1), ("one", 1),
-            ("2", 2), ("two", 2),
-            ("3", 3), ("three", 3),
-            ("4", 4), ("four", 4),
-            ("5", 5), ("five", 5),
-            ("6", 6), ("six", 6),
-            ("7", 7), ("seven", 7),
-            ("8", 8), ("eight", 8),
-            ("9", 9), ("nine", 9),
-        ];
-        let (_, a) = patterns.iter()
-            .filter_map(|(p, n)| Some((line.find(p)?, n)))
-            .min_by_key(|&(i, _)| i)
-            .unwrap();
-        let (_, b) = patterns.iter()
-            .filter_map(|(p, n)| Some((line.rfind(p)?, n)))
-            .max_by_key(|&(i, _)| i)
-            .unwrap();
-        sum += a * 10 + b;
+impl Automaton {
+    fn new<'p>(patterns: impl IntoIterator<Item = (&'p [u8], u32)>) -> Self {
+        let mut transitions = vec![[0; 256]];
+        let mut matches = vec![Vec::new()];
+        let mut max_len = 0;
+        for (pattern, value) in patterns {
+            let mut state = 0;
+            for &byte in pattern {
+                if transitions[state][byte as usize] == 0 {
+                    transitions[state][byte as usize] = transitions.len() as u32;
+                    transitions.push([0; 256]);
+                    matches.push(Vec::new());
+                }
+                state = transitions[state][byte as usize] as usize;
+            }
+            matches[state].push((pattern.len(), value));
+            max_len = max_len.max(pattern.len());
+        }
+
+        let mut fail = vec![0; transitions.len()];
+        let mut queue = std::collections::VecDeque::from([0]);
+        while let Some(state) = queue.pop_front() {
+            let children = transitions[state];
+            for (byte, &next) in children.iter().enumerate() {
+                let next = next as usize;
+                if next == 0 {
+                    if state != 0 {
+                        transitions[state][byte] = transitions[fail[state]][byte];
+                    }
+                    continue;
+                }
+                if state != 0 {
+                    fail[next] = transitions[fail[state]][byte] as usize;
+                    let inherited = matches[fail[next]].clone();
+                    matches[next].extend(inherited);
+                }
+                queue.push_back(next);
+            }
+        }
+        Self { transitions, matches, max_len }
+    }
+
+    // leftmost match, preferring the longest when several start at the same byte
+    fn leftmost(&self, bytes: impl Iterator<Item = u8>) -> Option<(usize, usize, u32)> {
+        let mut state = 0;
+        let mut best: Option<(usize, usize, u32)> = None;
+        for (i, byte) in bytes.enumerate() {
+            if best.is_some_and(|(start, _, _)| i >= start + self.max_len) {
+                break;
+            }
+            state = self.transitions[state][byte as usize] as usize;
+            for &(len, value) in &self.matches[state] {
+                let start = i + 1 - len;
+                if best.is_none_or(|(s, l, _)| start < s || (start == s && len > l)) {
+                    best = Some((start, len, value));
+                }
+            }
+        }
+        best
     }
+
+    // match ending at the earliest byte, preferring the longest when several end there
+    fn earliest_end(&self, bytes: impl Iterator<Item = u8>) -> Option<(usize, usize, u32)> {
+        let mut state = 0;
+        for (i, byte) in bytes.enumerate() {
+            state = self.transitions[state][byte as usize] as usize;
+            if let Some(&(len, value)) = self.matches[state].iter().max_by_key(|&&(len, _)| len) {
+                return Some((i + 1 - len, len, value));
+            }
+        }
+        None
+    }
+}
+
+struct Token<'l> {
+    text: &'l str,
+    offset: usize,
+    value: u32,
+}
+
+struct Calibration<'l> {
+    first: Token<'l>,
+    last: Token<'l>,
+    value: u32,
+}
+
+struct Decoder {
+    forwards: Automaton,
+    backwards: Automaton,
+}
+
+impl Decoder {
+    fn new<'p>(patterns: impl IntoIterator<Item = (&'p str, u32)> + Clone) -> Self {
+        let reversed = patterns.clone()
+            .into_iter()
+            .map(|(p, n)| (p.bytes().rev().collect::<Vec<_>>(), n))
+            .collect::<Vec<_>>();
+        Self {
+            forwards: Automaton::new(patterns.into_iter().map(|(p, n)| (p.as_bytes(), n))),
+            backwards: Automaton::new(reversed.iter().map(|(p, n)| (p.as_slice(), *n))),
+        }
+    }
+
+    // the first token starts leftmost and the last token starts rightmost, like
+    // find and rfind, with ties going to the longest word; values of more than one
+    // digit read as if they were written out in digits, so 'twenty-three' counts as
+    // a first digit of 2 and a last digit of 3
+    fn decode<'l>(&self, line: &'l str) -> Option<Calibration<'l>> {
+        let (start, len, value) = self.forwards.leftmost(line.bytes())?;
+        let first = Token { text: &line[start..start + len], offset: start, value };
+        // the rightmost start is the earliest end in the reversed line
+        let (rev_start, len, value) = self.backwards.earliest_end(line.bytes().rev())?;
+        let start = line.len() - rev_start - len;
+        let last = Token { text: &line[start..start + len], offset: start, value };
+
+        let mut a = first.value;
+        while a >= 10 {
+            a /= 10;
+        }
+        let value = a * 10 + last.value % 10;
+        Some(Calibration { first, last, value })
+    }
+}
+
+fn naive_calibration_value(line: &str) -> u32 {
+    let (_, a) = PATTERNS.iter()
+        .filter_map(|(p, n)| Some((line.find(p)?, n)))
+        .min_by_key(|&(i, _)| i)
+        .unwrap();
+    let (_, b) = PATTERNS.iter()
+        .filter_map(|(p, n)| Some((line.rfind(p)?, n)))
+        .max_by_key(|&(i, _)| i)
+        .unwrap();
+    a * 10 + b
+}
+
+fn calibration_sum(input: Lines, decoder: &Decoder) -> u32 {
+    let (sum, _) = input.fold((0, 0), |(sum, y), line| {
+        let Some(calibration) = decoder.decode(line) else {
+            aoc::fail(format!("line {} has no digits: '{}'", y + 1, line));
+        };
+        (sum + calibration.value, y + 1)
+    });
     sum
 }
 
-aoc::main!();
+fn part_1(input: Lines) -> u32 {
+    calibration_sum(input, &Vocabulary(digits()).decoder())
+}
+
+fn part_2(input: Lines) -> u32 {
+    calibration_sum(input, &params().vocabulary.decoder())
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_token(token: &Token) -> String {
+    format!(
+        "{{\"token\": {}, \"offset\": {}, \"value\": {}}}",
+        json_string(token.text), token.offset, token.value,
+    )
+}
+
+fn details(args: &Args) {
+    let vocabulary = match args.positional_or(1, 2) {
+        1 => Vocabulary(digits()),
+        2 => params().vocabulary.clone(),
+        _ => aoc::fail("Expected AoC part as argument (1 or 2)"),
+    };
+    let decoder = vocabulary.decoder();
+    let json = args.flag("json");
+
+    if json {
+        print!("{{\"lines\": [");
+    } else {
+        println!("{:>6}  {:<16} {:>6}  {:<16} {:>6}  {:>5}", "line", "first", "offset", "last", "offset", "value");
+    }
+    let (sum, errors, _) = aoc::stdin::<Lines>().fold((0_u64, 0, 0), |(sum, errors, y), line| {
+        let separator = if json && y > 0 { "," } else { "" };
+        let Some(calibration) = decoder.decode(line) else {
+            match json {
+                false => println!("{:>6}  error: no digits in '{}'", y + 1, line),
+                true => print!(
+                    "{}\n  {{\"line\": {}, \"text\": {}, \"error\": \"no digits\"}}",
+                    separator, y + 1, json_string(line),
+                ),
+            }
+            return (sum, errors + 1, y + 1);
+        };
+        let Calibration { first, last, value } = &calibration;
+        match json {
+            false => println!(
+                "{:>6}  {:<16} {:>6}  {:<16} {:>6}  {:>5}",
+                y + 1, first.text, first.offset, last.text, last.offset, value,
+            ),
+            true => print!(
+                "{}\n  {{\"line\": {}, \"text\": {}, \"first\": {}, \"last\": {}, \"value\": {}}}",
+                separator, y + 1, json_string(line), json_token(first), json_token(last), value,
+            ),
+        }
+        (sum + *value as u64, errors, y + 1)
+    });
+    match json {
+        false => println!("sum: {} ({} lines with errors)", sum, errors),
+        true => println!("\n], \"sum\": {}, \"errors\": {}}}", sum, errors),
+    }
+}
+
+fn generate_lines(count: usize) -> Vec<String> {
+    let mut seed = 0x2545f4914f6cdd1d_u64;
+    let mut random = move |n: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % n as u64) as usize
+    };
+    (0..count)
+        .map(|_| {
+            let mut line = String::new();
+            while line.len() < 40 || random(4) != 0 {
+                match random(3) {
+                    0 => line.push_str(PATTERNS[random(PATTERNS.len())].0),
+                    _ => line.push((b'a' + random(26) as u8) as char),
+                }
+            }
+            line.push_str(PATTERNS[random(PATTERNS.len())].0);
+            line
+        })
+        .collect()
+}
+
+fn bench(args: &Args) {
+    let lines = generate_lines(args.positional_or(1, 1_000_000));
+    let bytes = lines.iter().map(|l| l.len() + 1).sum::<usize>();
+    println!("{} generated lines, {} bytes", lines.len(), bytes);
+
+    let start = Instant::now();
+    let naive = lines.iter().map(|l| naive_calibration_value(l) as u64).sum::<u64>();
+    let naive_time = start.elapsed();
+    println!("find/rfind per pattern: {:?} (sum {})", naive_time, naive);
+
+    let start = Instant::now();
+    let decoder = Vocabulary::from_str("english").unwrap().decoder();
+    let automaton = lines.iter().map(|l| decoder.decode(l).unwrap().value as u64).sum::<u64>();
+    let automaton_time = start.elapsed();
+    println!("automaton: {:?} (sum {})", automaton_time, automaton);
+
+    assert_eq!(naive, automaton, "implementations disagree");
+    println!("speedup: {:.2}x", naive_time.as_secs_f64() / automaton_time.as_secs_f64());
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let decoder = params().vocabulary.decoder();
+    let mut diagnostics = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        if decoder.decode(line).is_none() {
+            let message = format!("no digits or digit words in '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        } else if !line.bytes().any(|b| b.is_ascii_digit()) {
+            diagnostics.push(Diagnostic::warning("no digits, so part 1 fails on this line").on_line(y));
+        }
+    }
+    diagnostics
+}
+
+aoc::main! {
+    params {
+        vocabulary: Vocabulary = "english".parse().unwrap(),
+    }
+    check: validate,
+    commands {
+        bench: bench,
+        details: details,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(vocabulary: &str, line: &str) -> u32 {
+        let vocabulary = vocabulary.parse::<Vocabulary>().unwrap();
+        vocabulary.decoder().decode(line).unwrap().value
+    }
+
+    #[test]
+    fn overlapping_words() {
+        assert_eq!(decode("english", "twone"), 21);
+        assert_eq!(decode("english", "eighthree"), 83);
+        assert_eq!(decode("english", "sevenine"), 79);
+    }
+
+    #[test]
+    fn overlapping_words_across_vocabularies() {
+        // french 'un' starts inside german 'neun', after it
+        assert_eq!(decode("german,french", "neun"), 91);
+        assert_eq!(decode("german", "neun"), 99);
+        assert_eq!(decode("english,french", "sixx"), 66);
+    }
+
+    #[test]
+    fn default_vocabulary_has_no_zero() {
+        assert_eq!(decode("english", "0one20"), 12);
+        assert_eq!(decode("german", "0null7"), 7);
+        assert_eq!(Vocabulary(digits()).decoder().decode("0one20").unwrap().value, 0);
+    }
+
+    #[test]
+    fn compound_numbers() {
+        assert_eq!(decode("numbers", "twenty-three"), 23);
+        assert_eq!(decode("numbers", "xtwenty"), 20);
+        assert_eq!(decode("numbers", "ninety-nine4twelve"), 92);
+    }
+
+    #[test]
+    fn matches_naive_search() {
+        for line in ["two1nine", "eightwothree", "abcone2threexyz", "xtwone3four", "4nineeightseven2", "zoneight234", "7pqrstsixteen"] {
+            let decoded = Vocabulary(PATTERNS.iter().map(|&(w, n)| (w.to_owned(), n)).collect())
+                .decoder()
+                .decode(line)
+                .unwrap()
+                .value;
+            assert_eq!(decoded, naive_calibration_value(line), "{}", line);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_words() {
+        let words = Vocabulary::parse_words(" 5").unwrap();
+        assert!(Vocabulary::new(words).is_err());
+    }
+
+    #[test]
+    fn rejects_conflicting_duplicates() {
+        let words = Vocabulary::parse_words("uno 1\nuno 2").unwrap();
+        assert!(Vocabulary::new(words).is_err());
+        let words = Vocabulary::parse_words("uno 1\nuno 1").unwrap();
+        assert!(Vocabulary::new(words).is_ok());
+    }
+}
@@ -1,55 +1,468 @@
-fn part_1(input: String) -> u32 {
-    let mut sum = 0;
-    'iter: for line in input.lines() {
-        let (game_str, subsets) = line.split_once(": ").unwrap();
-        let game_id = game_str.strip_prefix("Game ").unwrap();
-        let game_id = game_id.parse::<u32>().unwrap();
-
-        for subset in subsets.split("; ") {
-            let mut red = 0;
-            let mut green = 0;
-            let mut blue = 0;
-            for cubes in subset.split(", ") {
-                let (count, color) = cubes.split_once(' ').unwrap();
-                let count = count.parse::<u32>().unwrap();
-                match color {
-                    "red" => red += count,
-                    "green" => green += count,
-                    "blue" => blue += count,
-                    _ => panic!()
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::str::FromStr;
+use aoc::{Args, Diagnostic, Lines};
+
+type Colour = String;
+
+fn parse_cubes(cubes: &str) -> Option<HashMap<Colour, u32>> {
+    let mut counts = HashMap::new();
+    for cubes in cubes.split(", ") {
+        let (count, colour) = cubes.trim().split_once(' ')?;
+        let count = count.parse::<u32>().ok()?;
+        *counts.entry(colour.to_owned()).or_insert(0) += count;
+    }
+    Some(counts)
+}
+
+#[derive(Debug, Clone)]
+struct Bag(HashMap<Colour, u32>);
+
+impl FromStr for Bag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_cubes(s)
+            .map(Self)
+            .ok_or_else(|| format!("expected cubes such as '12 red, 13 green', got '{}'", s))
+    }
+}
+
+impl Display for Bag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut cubes = self.0.iter().collect::<Vec<_>>();
+        cubes.sort_unstable();
+        let cubes = cubes.iter().map(|(c, n)| format!("{} {}", n, c)).collect::<Vec<_>>();
+        write!(f, "{}", cubes.join(", "))
+    }
+}
+
+impl Bag {
+    fn count(&self, colour: &str) -> u32 {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
+
+    // a colour that's expected but missing counts as 0 cubes
+    fn power(&self, expected: &Bag) -> u32 {
+        let colours = expected.0.keys().chain(self.0.keys()).collect::<HashSet<_>>();
+        colours.into_iter().map(|c| self.count(c)).product()
+    }
+
+    fn total(&self) -> u32 {
+        self.0.values().sum()
+    }
+
+    fn union(mut self, other: &Bag) -> Self {
+        for (colour, &count) in &other.0 {
+            let max = self.0.entry(colour.clone()).or_insert(0);
+            *max = count.max(*max);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Game {
+    id: u32,
+    rounds: Vec<HashMap<Colour, u32>>,
+}
+
+impl Game {
+    fn parse(line: &str) -> Self {
+        let (game_str, rounds) = line.split_once(": ").unwrap();
+        let id = game_str.strip_prefix("Game ").unwrap().parse().unwrap();
+        let rounds = rounds.split("; ").map(|r| parse_cubes(r).unwrap()).collect();
+        Self { id, rounds }
+    }
+
+    fn possible_with(&self, bag: &Bag) -> bool {
+        self.rounds.iter().all(|round| round.iter().all(|(c, &n)| n <= bag.count(c)))
+    }
+
+    fn min_bag(&self) -> Bag {
+        self.rounds.iter().fold(Bag(HashMap::new()), |bag, round| bag.union(&Bag(round.clone())))
+    }
+}
+
+// little-endian base 2^32 digits; only what's needed to print and compare ratios
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BigUint(Vec<u32>);
+
+impl BigUint {
+    fn from_factors(factors: impl Iterator<Item = (u32, i32)>) -> Self {
+        let mut n = Self(vec![1]);
+        for (prime, exponent) in factors {
+            for _ in 0..exponent {
+                let mut carry = 0;
+                for digit in &mut n.0 {
+                    let product = *digit as u64 * prime as u64 + carry;
+                    *digit = product as u32;
+                    carry = product >> 32;
+                }
+                if carry > 0 {
+                    n.0.push(carry as u32);
                 }
             }
-            if red > 12 || green > 13 || blue > 14 {
-                continue 'iter;
+        }
+        n
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.len().cmp(&other.0.len()).then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut n = self.0.clone();
+        let mut chunks = Vec::new();
+        while n.iter().any(|&d| d != 0) {
+            let mut remainder = 0;
+            for digit in n.iter_mut().rev() {
+                let value = (remainder << 32) | *digit as u64;
+                *digit = (value / 1_000_000_000) as u32;
+                remainder = value % 1_000_000_000;
+            }
+            chunks.push(remainder);
+        }
+        match chunks.split_last() {
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                rest.iter().rev().try_for_each(|c| write!(f, "{:09}", c))
+            }
+            None => write!(f, "0"),
+        }
+    }
+}
+
+// a positive rational stored as prime exponents, so it's always exactly reduced
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Ratio(BTreeMap<u32, i32>);
+
+impl Ratio {
+    fn mul_factorial(&mut self, n: u32, sign: i32) {
+        for prime in (2..=n).filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0)) {
+            let mut exponent = 0;
+            let mut power = prime;
+            while power <= n {
+                exponent += (n / power) as i32;
+                power = power.saturating_mul(prime);
+            }
+            let entry = self.0.entry(prime).or_insert(0);
+            *entry += sign * exponent;
+            if *entry == 0 {
+                self.0.remove(&prime);
             }
         }
-        sum += game_id;
     }
-    sum
+
+    fn mul_binomial(&mut self, n: u32, k: u32, sign: i32) {
+        self.mul_factorial(n, sign);
+        self.mul_factorial(k, -sign);
+        self.mul_factorial(n - k, -sign);
+    }
+
+    fn mul(&mut self, other: &Ratio) {
+        for (&prime, &exponent) in &other.0 {
+            let entry = self.0.entry(prime).or_insert(0);
+            *entry += exponent;
+            if *entry == 0 {
+                self.0.remove(&prime);
+            }
+        }
+    }
+
+    fn numerator(&self) -> BigUint {
+        BigUint::from_factors(self.0.iter().map(|(&p, &e)| (p, e.max(0))))
+    }
+
+    fn denominator(&self) -> BigUint {
+        BigUint::from_factors(self.0.iter().map(|(&p, &e)| (p, (-e).max(0))))
+    }
+
+    fn log10(&self) -> f64 {
+        self.0.iter().map(|(&p, &e)| e as f64 * (p as f64).log10()).sum()
+    }
 }
 
-fn part_2(input: String) -> u32 {
-    let mut sum = 0;
-    for line in input.lines() {
-        let (_, subsets) = line.split_once(": ").unwrap();
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
-        for subset in subsets.split("; ") {
-            for cubes in subset.split(", ") {
-                let (count, color) = cubes.split_once(' ').unwrap();
-                let count = count.parse::<u32>().unwrap();
-                match color {
-                    "red" => red = red.max(count),
-                    "green" => green = green.max(count),
-                    "blue" => blue = blue.max(count),
-                    _ => panic!()
+impl Ord for Ratio {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut quotient = self.clone();
+        quotient.mul(&Ratio(other.0.iter().map(|(&p, &e)| (p, -e)).collect()));
+        quotient.numerator().cmp(&quotient.denominator())
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let log10 = self.log10();
+        let exponent = log10.floor();
+        let mantissa = 10f64.powf(log10 - exponent);
+        write!(f, "{}/{} (~{:.4}e{})", self.numerator(), self.denominator(), mantissa, exponent as i64)
+    }
+}
+
+// None is a probability of zero
+fn round_probability(bag: &Bag, round: &HashMap<Colour, u32>) -> Option<Ratio> {
+    let total = bag.0.values().sum::<u32>();
+    let drawn = round.values().sum::<u32>();
+    if drawn > total {
+        return None;
+    }
+    let mut probability = Ratio::default();
+    for (colour, &count) in round {
+        let available = bag.count(colour);
+        if count > available {
+            return None;
+        }
+        probability.mul_binomial(available, count, 1);
+    }
+    probability.mul_binomial(total, drawn, -1);
+    Some(probability)
+}
+
+fn game_probability(bag: &Bag, game: &Game) -> Option<Ratio> {
+    game.rounds.iter().try_fold(Ratio::default(), |mut probability, round| {
+        probability.mul(&round_probability(bag, round)?);
+        Some(probability)
+    })
+}
+
+fn likelihood(bag: &Bag, games: &[Game]) -> Option<Ratio> {
+    games.iter().try_fold(Ratio::default(), |mut probability, game| {
+        probability.mul(&game_probability(bag, game)?);
+        Some(probability)
+    })
+}
+
+fn read_games() -> Vec<Game> {
+    aoc::stdin::<String>().lines().map(Game::parse).collect()
+}
+
+fn probability(_: &Args) {
+    let bag = &params().bag;
+    let games = read_games();
+    println!("bag: {}", bag);
+    for game in &games {
+        match game_probability(bag, game) {
+            Some(p) => println!("Game {}: {}", game.id, p),
+            None => println!("Game {}: 0", game.id),
+        }
+    }
+    match likelihood(bag, &games) {
+        Some(p) => println!("all games: {}", p),
+        None => println!("all games: 0"),
+    }
+}
+
+fn rank(args: &Args) {
+    let mut bags = (1..)
+        .map_while(|i| args.positional(i))
+        .map(|b| b.parse::<Bag>().unwrap_or_else(|e| aoc::fail(e)))
+        .collect::<Vec<_>>();
+    if bags.is_empty() {
+        bags.push(params().bag.clone());
+    }
+
+    let games = read_games();
+    let mut ranked = bags.iter()
+        .map(|bag| (likelihood(bag, &games), bag))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+    for (i, (likelihood, bag)) in ranked.iter().enumerate() {
+        match likelihood {
+            Some(p) => println!("{}. {}: {}", i + 1, bag, p),
+            None => println!("{}. {}: 0", i + 1, bag),
+        }
+    }
+}
+
+// every bag of exactly `total` cubes holding at least the minimum of each colour
+fn consistent_bags(min: &Bag, total: u32) -> Vec<Bag> {
+    fn distribute(colours: &[(&Colour, u32)], remaining: u32, bag: &mut Bag, bags: &mut Vec<Bag>) {
+        let Some((&(colour, min), colours)) = colours.split_first() else {
+            if remaining == 0 {
+                bags.push(bag.clone());
+            }
+            return;
+        };
+        // the last colour takes whatever's left over
+        let counts = match colours.is_empty() {
+            true => remaining..=remaining,
+            false => 0..=remaining,
+        };
+        for extra in counts {
+            bag.0.insert(colour.clone(), min + extra);
+            distribute(colours, remaining - extra, bag, bags);
+        }
+    }
+
+    let mut bags = Vec::new();
+    let mut colours = min.0.iter().map(|(c, &n)| (c, n)).collect::<Vec<_>>();
+    colours.sort_unstable();
+    if let Some(remaining) = total.checked_sub(min.total()) {
+        distribute(&colours, remaining, &mut Bag(HashMap::new()), &mut bags);
+    }
+    bags
+}
+
+fn minimal(_: &Args) {
+    let games = read_games();
+    let bag = games.iter().fold(Bag(HashMap::new()), |bag, game| bag.union(&game.min_bag()));
+    println!("{} ({} cubes, power {})", bag, bag.total(), bag.power(&params().bag));
+}
+
+fn bags(args: &Args) {
+    let Some(total) = args.positional(1) else {
+        aoc::fail("Expected a total number of cubes, optionally followed by game ids");
+    };
+    let total = total.parse().unwrap_or_else(|e| aoc::fail(format!("Invalid total '{}': {}", total, e)));
+    let ids = (2..)
+        .map_while(|i| args.positional(i))
+        .map(|id| id.parse::<u32>().unwrap_or_else(|e| aoc::fail(format!("Invalid game id '{}': {}", id, e))))
+        .collect::<Vec<_>>();
+
+    let games = read_games();
+    let selected = games.iter()
+        .filter(|g| ids.is_empty() || ids.contains(&g.id))
+        .collect::<Vec<_>>();
+    if let Some(id) = ids.iter().find(|&&id| !games.iter().any(|g| g.id == id)) {
+        aoc::fail(format!("No game with id {}", id));
+    }
+
+    let min = selected.iter().fold(Bag(HashMap::new()), |bag, game| bag.union(&game.min_bag()));
+    let bags = consistent_bags(&min, total);
+    for bag in &bags {
+        println!("{}", bag);
+    }
+    println!("{} bags of {} cubes make all {} games possible", bags.len(), total, selected.len());
+}
+
+fn part_1(input: Lines) -> u32 {
+    input.fold(0, |sum, line| {
+        let game = Game::parse(line);
+        match game.possible_with(&params().bag) {
+            true => sum + game.id,
+            false => sum,
+        }
+    })
+}
+
+fn part_2(input: Lines) -> u32 {
+    input.fold(0, |sum, line| sum + Game::parse(line).min_bag().power(&params().bag))
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        let Some((game, rounds)) = line.split_once(": ") else {
+            let message = format!("expected 'Game <id>: <rounds>', got '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        if game.strip_prefix("Game ").and_then(|id| id.parse::<u32>().ok()).is_none() {
+            diagnostics.push(Diagnostic::error(format!("invalid game id '{}'", game)).on_line(y));
+        }
+        let mut unknown = Vec::new();
+        for round in rounds.split("; ") {
+            match parse_cubes(round) {
+                Some(cubes) => unknown.extend(cubes.into_keys().filter(|c| params().bag.count(c) == 0)),
+                None => {
+                    let message = format!("expected cubes such as '3 blue, 4 red', got '{}'", round);
+                    diagnostics.push(Diagnostic::error(message).on_line(y));
                 }
             }
         }
-        sum += red * green * blue;
+        unknown.sort_unstable();
+        unknown.dedup();
+        for colour in unknown {
+            let message = format!("the bag has no {} cubes, so this game is never possible", colour);
+            diagnostics.push(Diagnostic::warning(message).on_line(y));
+        }
+    }
+    diagnostics
+}
+
+aoc::main! {
+    params {
+        bag: Bag = "12 red, 13 green, 14 blue".parse().unwrap(),
+    }
+    check: validate,
+    commands {
+        probability: probability,
+        rank: rank,
+        minimal: minimal,
+        bags: bags,
     }
-    sum
 }
 
-aoc::main!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numerator: u32, denominator: u32) -> Ratio {
+        let mut ratio = Ratio::default();
+        for (mut n, sign) in [(numerator, 1), (denominator, -1)] {
+            let mut factor = 2;
+            while n > 1 {
+                while n % factor == 0 {
+                    ratio.mul(&Ratio(BTreeMap::from([(factor, sign)])));
+                    n /= factor;
+                }
+                factor += 1;
+            }
+        }
+        ratio
+    }
+
+    fn cubes(s: &str) -> HashMap<Colour, u32> {
+        parse_cubes(s).unwrap()
+    }
+
+    #[test]
+    fn big_uint_display() {
+        let show = |factors: &[(u32, i32)]| BigUint::from_factors(factors.iter().copied()).to_string();
+        assert_eq!(show(&[]), "1");
+        assert_eq!(show(&[(2, 32)]), "4294967296");
+        assert_eq!(show(&[(2, 9), (5, 9)]), "1000000000");
+        assert_eq!(show(&[(2, 9), (5, 9), (7, 1)]), "7000000000");
+        assert_eq!(show(&[(2, 64)]), "18446744073709551616");
+        assert_eq!(show(&[(2, 18), (5, 18), (3, 1)]), "3000000000000000000");
+    }
+
+    #[test]
+    fn ratio_cmp() {
+        assert_eq!(ratio(2, 4), ratio(1, 2));
+        assert_eq!(ratio(1000, 999).cmp(&ratio(1001, 1000)), Ordering::Greater);
+        assert_eq!(ratio(999_999, 1_000_000).cmp(&ratio(1_000_000, 1_000_001)), Ordering::Less);
+        assert_eq!(ratio(6, 10).cmp(&ratio(3, 5)), Ordering::Equal);
+    }
+
+    #[test]
+    fn round_probability_is_hypergeometric() {
+        let bag = "1 red, 1 blue".parse::<Bag>().unwrap();
+        assert_eq!(round_probability(&bag, &cubes("1 red")), Some(ratio(1, 2)));
+        assert_eq!(round_probability(&bag, &cubes("1 red, 1 blue")), Some(ratio(1, 1)));
+        assert_eq!(round_probability(&bag, &cubes("2 red")), None);
+
+        // C(2, 1) * C(3, 1) / C(5, 2)
+        let bag = "2 red, 3 blue".parse::<Bag>().unwrap();
+        assert_eq!(round_probability(&bag, &cubes("1 red, 1 blue")), Some(ratio(3, 5)));
+        assert_eq!(round_probability(&bag, &cubes("1 green")), None);
+    }
+}
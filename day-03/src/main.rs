@@ -1,5 +1,9 @@
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 use std::collections::HashMap;
+use std::str::FromStr;
+use aoc::{Args, Diagnostic, Repl};
+
+type Cell = (i32, i32);
 
 fn all_nums(grid: &[&str]) -> Vec<(i32, Range<i32>, u32)> {
     let mut nums = Vec::new();
@@ -37,49 +41,297 @@ fn neighbours(x: i32, y: i32) -> [(i32, i32); 8] {
     ]
 }
 
-fn part_1(input: String) -> u32 {
-    let grid = input.lines().collect::<Vec<_>>();
-    let cell = |x: i32, y: i32| Some(
-        *grid
-            .get(usize::try_from(y).ok()?)?
-            .as_bytes()
-            .get(usize::try_from(x).ok()?)?
-    );
-
-    all_nums(&grid).iter()
-        .filter(|(y, xs, _)| {
-            xs.clone().any(|x| {
-                neighbours(x, *y).iter()
-                    .filter_map(|&(nx, ny)| cell(nx, ny))
-                    .any(|c| !c.is_ascii_digit() && c != b'.')
+// numbers and symbols as the two sides of a bipartite graph, linked when adjacent
+struct Schematic {
+    numbers: Vec<(i32, Range<i32>, u32)>,
+    symbols: HashMap<Cell, char>,
+    number_symbols: Vec<Vec<Cell>>,
+    symbol_numbers: HashMap<Cell, Vec<usize>>,
+}
+
+impl Schematic {
+    fn parse(input: &str) -> Self {
+        let grid = input.lines().collect::<Vec<_>>();
+        let numbers = all_nums(&grid);
+        let symbols = grid.iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| !c.is_ascii_digit() && c != '.')
+                    .map(move |(x, c)| ((x as i32, y as i32), c))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut number_symbols = Vec::new();
+        let mut symbol_numbers = HashMap::<_, Vec<_>>::new();
+        for (i, (y, xs, _)) in numbers.iter().enumerate() {
+            let mut adjacent = xs.clone()
+                .flat_map(|x| neighbours(x, *y))
+                .filter(|cell| symbols.contains_key(cell))
+                .collect::<Vec<_>>();
+            adjacent.sort_unstable();
+            adjacent.dedup();
+            for &cell in &adjacent {
+                symbol_numbers.entry(cell).or_default().push(i);
+            }
+            number_symbols.push(adjacent);
+        }
+        Self { numbers, symbols, number_symbols, symbol_numbers }
+    }
+
+    fn parts_adjacent(&self, cell: Cell) -> impl Iterator<Item = u32> + '_ {
+        self.symbol_numbers.get(&cell)
+            .into_iter()
+            .flatten()
+            .map(|&i| self.numbers[i].2)
+    }
+
+    fn symbols_touching(&self, number: u32) -> impl Iterator<Item = (Cell, char)> + '_ {
+        self.numbers.iter()
+            .zip(&self.number_symbols)
+            .filter(move |((_, _, n), _)| *n == number)
+            .flat_map(|(_, cells)| cells.iter().map(|c| (*c, self.symbols[c])))
+    }
+
+    fn part_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.numbers.iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|((_, _, n), _)| *n)
+    }
+
+    fn gears(&self, symbol: char, parts: RangeInclusive<usize>) -> impl Iterator<Item = (Cell, Vec<u32>)> + '_ {
+        self.symbols.iter()
+            .filter(move |&(cell, &s)| {
+                let count = self.symbol_numbers.get(cell).map_or(0, Vec::len);
+                s == symbol && parts.contains(&count)
+            })
+            .map(|(&cell, _)| (cell, self.parts_adjacent(cell).collect()))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Combine {
+    Product,
+    Sum,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    symbol: char,
+    parts: RangeInclusive<usize>,
+    combine: Combine,
+}
+
+impl Rule {
+    fn combine(&self, parts: &[u32]) -> u32 {
+        let parts = parts.iter().copied();
+        match self.combine {
+            Combine::Product => parts.product(),
+            Combine::Sum => parts.sum(),
+            Combine::Min => parts.min().unwrap_or(0),
+            Combine::Max => parts.max().unwrap_or(0),
+        }
+    }
+}
+
+// comma separated 'symbol:parts:operation' rules, where parts is an exact count
+// or a minimum like '2+', e.g. '*:2:product,#:1+:sum'
+#[derive(Debug, Clone)]
+struct Rules(Vec<Rule>);
+
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|rule| {
+                let invalid = || format!("expected 'symbol:parts:operation', got '{}'", rule);
+                let mut fields = rule.splitn(3, ':');
+                let (Some(symbol), Some(parts), Some(combine)) = (fields.next(), fields.next(), fields.next()) else {
+                    return Err(invalid());
+                };
+                let mut symbol_chars = symbol.chars();
+                let (Some(symbol), None) = (symbol_chars.next(), symbol_chars.next()) else {
+                    return Err(invalid());
+                };
+                let parts = match parts.strip_suffix('+') {
+                    Some(min) => min.parse().map_err(|_| invalid())?..=usize::MAX,
+                    None => {
+                        let count = parts.parse().map_err(|_| invalid())?;
+                        count..=count
+                    }
+                };
+                let combine = match combine {
+                    "product" => Combine::Product,
+                    "sum" => Combine::Sum,
+                    "min" => Combine::Min,
+                    "max" => Combine::Max,
+                    _ => return Err(format!("unknown operation '{}' (expected product, sum, min or max)", combine)),
+                };
+                Ok(Rule { symbol, parts, combine })
             })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+fn part_1(input: String) -> u32 {
+    Schematic::parse(&input).part_numbers().sum()
+}
+
+fn part_2(input: String) -> u32 {
+    let schematic = Schematic::parse(&input);
+    let Rules(rules) = &params().rules;
+    rules.iter()
+        .flat_map(|rule| {
+            schematic.gears(rule.symbol, rule.parts.clone()).map(|(_, parts)| rule.combine(&parts))
         })
-        .map(|(_, _, n)| n)
         .sum()
 }
 
-fn part_2(input: String) -> u32 {
-    let grid = input.lines().collect::<Vec<_>>();
-    let cell = |x: i32, y: i32| Some(
-        *grid
-            .get(usize::try_from(y).ok()?)?
-            .as_bytes()
-            .get(usize::try_from(x).ok()?)?
-    );
-    
-    let mut gears = HashMap::new();
-    for (y, xs, n) in all_nums(&grid) {
-        for (nx, ny) in xs.clone().flat_map(|x| neighbours(x, y)) {
-            if cell(nx, ny) == Some(b'*') {
-                let (c, r) = gears.entry((nx, ny)).or_insert((0, 1));
-                *c += 1;
-                *r *= n;
-                break;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Plain,
+    Part,
+    Orphan,
+    Gear,
+}
+
+fn render(args: &Args) {
+    let input = aoc::stdin::<String>();
+    let schematic = Schematic::parse(&input);
+    let html = args.flag("html");
+
+    let mut styles = HashMap::new();
+    for ((y, xs, _), symbols) in schematic.numbers.iter().zip(&schematic.number_symbols) {
+        let style = if symbols.is_empty() { Style::Orphan } else { Style::Part };
+        styles.extend(xs.clone().map(|x| ((x, *y), style)));
+    }
+    let mut ratios = HashMap::<_, Vec<_>>::new();
+    for rule in &params().rules.0 {
+        for (cell, parts) in schematic.gears(rule.symbol, rule.parts.clone()) {
+            styles.insert(cell, Style::Gear);
+            ratios.entry(cell).or_default().push(rule.combine(&parts));
+        }
+    }
+
+    if html {
+        println!("<!DOCTYPE html>");
+        println!("<style>.part {{ color: green; }} .orphan {{ color: red; }} .gear {{ color: orange; font-weight: bold; }}</style>");
+        println!("<pre>");
+    }
+    for (y, row) in input.lines().enumerate() {
+        let mut rendered = String::new();
+        let mut style = Style::Plain;
+        for (x, c) in row.chars().enumerate() {
+            let cell = (x as i32, y as i32);
+            let cell_style = styles.get(&cell).copied().unwrap_or(Style::Plain);
+            if cell_style != style || cell_style == Style::Gear {
+                match (html, style) {
+                    (_, Style::Plain) => {}
+                    (false, _) => rendered.push_str("\x1b[0m"),
+                    (true, _) => rendered.push_str("</span>"),
+                }
+                match (html, cell_style) {
+                    (_, Style::Plain) => {}
+                    (false, Style::Part) => rendered.push_str("\x1b[32m"),
+                    (false, Style::Orphan) => rendered.push_str("\x1b[31m"),
+                    (false, Style::Gear) => rendered.push_str("\x1b[1;33m"),
+                    (true, Style::Part) => rendered.push_str("<span class=\"part\">"),
+                    (true, Style::Orphan) => rendered.push_str("<span class=\"orphan\">"),
+                    (true, Style::Gear) => {
+                        let ratio = ratios[&cell].iter().map(|r| r.to_string()).collect::<Vec<_>>();
+                        rendered.push_str(&format!("<span class=\"gear\" title=\"{}\">", ratio.join(", ")));
+                    }
+                }
+                style = cell_style;
+            }
+            match (html, c) {
+                (true, '<') => rendered.push_str("&lt;"),
+                (true, '>') => rendered.push_str("&gt;"),
+                (true, '&') => rendered.push_str("&amp;"),
+                _ => rendered.push(c),
+            }
+        }
+        match (html, style) {
+            (_, Style::Plain) => {}
+            (false, _) => rendered.push_str("\x1b[0m"),
+            (true, _) => rendered.push_str("</span>"),
+        }
+
+        let mut row_gears = ratios.iter().filter(|&(&(_, gy), _)| gy == y as i32).collect::<Vec<_>>();
+        row_gears.sort_unstable();
+        for ((x, _), ratios) in row_gears {
+            let ratios = ratios.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+            rendered.push_str(&format!("  x={}: {}", x, ratios.join(", ")));
+        }
+        println!("{}", rendered);
+    }
+    if html {
+        println!("</pre>");
+    }
+}
+
+fn repl(input: &str) -> Repl<'_, Schematic> {
+    Repl::new(input, Schematic::parse)
+        .command("parts", "<x> <y>", |schematic, args| {
+            let cell = (aoc::repl::arg(args, 0)?, aoc::repl::arg(args, 1)?);
+            let symbol = schematic.symbols.get(&cell).ok_or_else(|| format!("no symbol at {:?}", cell))?;
+            let parts = schematic.parts_adjacent(cell).collect::<Vec<_>>();
+            println!("'{}' at {:?} touches {:?}", symbol, cell, parts);
+            Ok(())
+        })
+        .command("symbols", "<number>", |schematic, args| {
+            let number = aoc::repl::arg(args, 0)?;
+            for ((x, y), symbol) in schematic.symbols_touching(number) {
+                println!("'{}' at ({}, {})", symbol, x, y);
+            }
+            Ok(())
+        })
+        .command("gears", "<symbol> <parts>", |schematic, args| {
+            let symbol = aoc::repl::arg(args, 0)?;
+            let parts = aoc::repl::arg::<usize>(args, 1)?;
+            let mut gears = schematic.gears(symbol, parts..=parts).collect::<Vec<_>>();
+            gears.sort_unstable();
+            for ((x, y), parts) in gears {
+                println!("({}, {}): {:?}", x, y, parts);
+            }
+            Ok(())
+        })
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = aoc::check_grid(input, |c| c.is_ascii_graphic());
+    for (y, row) in input.lines().enumerate() {
+        let mut digits = row.char_indices().peekable();
+        while let Some((start, c)) = digits.next() {
+            if !c.is_ascii_digit() {
+                continue;
+            }
+            let mut end = start + 1;
+            while let Some((x, _)) = digits.next_if(|(_, c)| c.is_ascii_digit()) {
+                end = x + 1;
+            }
+            if row[start..end].parse::<u32>().is_err() {
+                let message = format!("number {} is too large", &row[start..end]);
+                diagnostics.push(Diagnostic::error(message).at(y, start));
             }
         }
     }
-    
-    gears.values().filter(|&&(c, _)| c >= 2).map(|(_, n)| n).sum()
+    diagnostics
 }
 
-aoc::main!();
+aoc::main! {
+    params {
+        rules: Rules = "*:2:product".parse().unwrap(),
+    }
+    check: validate,
+    repl: repl,
+    commands {
+        render: render,
+    }
+}
@@ -1,25 +1,207 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::str::FromStr;
+use aoc::{Args, Diagnostic, Lines};
+
+fn parse_nums(nums: &str) -> impl Iterator<Item = u32> + '_ {
+    nums.split_ascii_whitespace().map(|n| n.parse().unwrap())
+}
+
 fn card_matches(card_str: &str) -> usize {
     let (_, numbers) = card_str.split_once(": ").unwrap();
     let (winning_nums, our_nums) = numbers.split_once(" | ").unwrap();
-    let winning_nums = winning_nums.split_ascii_whitespace().collect::<Vec<_>>();
-    our_nums.split_ascii_whitespace().filter(|n| winning_nums.contains(n)).count()
+    let winning_nums = parse_nums(winning_nums).collect::<HashSet<_>>();
+    parse_nums(our_nums).filter(|n| winning_nums.contains(n)).count()
+}
+
+// what happens to copies won of cards past the last one
+#[derive(Debug, Clone, Copy)]
+enum Overflow {
+    // they're dropped
+    Clamp,
+    // the run fails
+    Error,
+    // they wrap round to the first cards and are counted once, but don't win more copies
+    Wrap,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(Self::Clamp),
+            "error" => Ok(Self::Error),
+            "wrap" => Ok(Self::Wrap),
+            _ => Err(format!(
+                "expected clamp (drop copies past the last card), error (fail on them) \
+                 or wrap (count them once, without winning more copies), got '{}'",
+                s,
+            )),
+        }
+    }
+}
+
+// a difference array over the cards still to come, so memory is bounded by the
+// largest match count; ends[k] holds the copies that stop being won k cards
+// from now, and the first card that won them
+struct Cascade {
+    ends: VecDeque<(u32, usize)>,
+    won: u32,
+    cards: usize,
+}
+
+impl Cascade {
+    fn new() -> Self {
+        Self { ends: VecDeque::new(), won: 0, cards: 0 }
+    }
+
+    // returns how many copies of the next card are held
+    fn push(&mut self, matches: usize) -> u32 {
+        let (ended, _) = self.ends.pop_front().unwrap_or((0, 0));
+        self.won -= ended;
+        let count = 1 + self.won;
+        if self.ends.len() <= matches {
+            self.ends.resize(matches + 1, (0, usize::MAX));
+        }
+        let (copies, card) = &mut self.ends[matches];
+        *copies += count;
+        *card = (*card).min(self.cards);
+        self.won += count;
+        self.cards += 1;
+        count
+    }
+
+    // settles copies won past the last card, returning how many are counted
+    fn finish(self, overflow: Overflow) -> u32 {
+        // the front of the window is one past the last card
+        let past_end = self.ends.iter().enumerate().skip(1).filter(|(_, &(copies, _))| copies > 0);
+        match overflow {
+            Overflow::Clamp => 0,
+            Overflow::Error => match past_end.min_by_key(|(_, &(_, card))| card) {
+                Some((past, &(_, card))) => aoc::fail(format!(
+                    "card {} wins copies of {} card(s) past the last card",
+                    card + 1,
+                    past,
+                )),
+                None => 0,
+            },
+            Overflow::Wrap => past_end.map(|(past, &(copies, _))| copies * past as u32).sum(),
+        }
+    }
 }
 
-fn part_1(input: String) -> u32 {
-    input.lines().map(card_matches).filter(|&m| m > 0).map(|m| 1 << m - 1).sum()
+fn part_1(input: Lines) -> u32 {
+    input.fold(0, |sum, card| match card_matches(card) {
+        0 => sum,
+        m => sum + (1 << (m - 1)),
+    })
 }
 
-fn part_2(input: String) -> u32 {
-    let mut cards = input.lines()
-        .map(|c| (1, card_matches(c)))
+fn part_2(input: Lines) -> u32 {
+    let (cascade, sum) = input.fold((Cascade::new(), 0), |(mut cascade, sum), card| {
+        let count = cascade.push(card_matches(card));
+        (cascade, sum + count)
+    });
+    sum + cascade.finish(params().overflow)
+}
+
+fn stats(_: &Args) {
+    let overflow = params().overflow;
+    let (cascade, cards) = aoc::stdin::<Lines>().fold((Cascade::new(), Vec::new()), |(mut cascade, mut cards), card| {
+        let matches = card_matches(card);
+        cards.push((matches, cascade.push(matches)));
+        (cascade, cards)
+    });
+    let wrapped = cascade.finish(overflow);
+
+    let mut distribution = BTreeMap::new();
+    for &(m, _) in &cards {
+        *distribution.entry(m).or_insert(0) += 1;
+    }
+    println!("matches  cards");
+    for (m, count) in distribution {
+        println!("{:7}  {:5}", m, count);
+    }
+
+    // cards won past the last one, which only wrapping keeps
+    let won = |i: usize, m: usize| match overflow {
+        Overflow::Wrap => m,
+        _ => m.min(cards.len() - i - 1),
+    };
+
+    // copies each card hands out to the cards after it
+    let generated = cards.iter()
+        .enumerate()
+        .map(|(i, &(m, count))| count as u64 * won(i, m) as u64)
         .collect::<Vec<_>>();
-    for i in 0..cards.len() {
-        let (count, matches) = cards[i];
-        for j in 0..matches {
-            cards[i + 1 + j].0 += count;
+    let mut ranked = (0..cards.len()).collect::<Vec<_>>();
+    ranked.sort_by_key(|&i| std::cmp::Reverse(generated[i]));
+    println!();
+    println!("card  copies generated");
+    for &i in ranked.iter().take(10) {
+        println!("{:4}  {:16}", i + 1, generated[i]);
+    }
+
+    // cards ultimately produced by a single copy of each card, itself included,
+    // from suffix sums so each card is O(1)
+    let mut produced = vec![0u64; cards.len()];
+    let mut suffix = vec![0u64; cards.len() + 1];
+    for (i, &(m, _)) in cards.iter().enumerate().rev() {
+        let end = (i + 1 + m).min(cards.len());
+        let wrapped = (won(i, m) + i + 1 - end) as u64;
+        produced[i] = 1 + suffix[i + 1] - suffix[end] + wrapped;
+        suffix[i] = suffix[i + 1] + produced[i];
+    }
+    println!();
+    println!("card  cards produced");
+    for (i, produced) in produced.iter().enumerate() {
+        println!("{:4}  {:14}", i + 1, produced);
+    }
+
+    let total = cards.iter().map(|&(_, count)| count).sum::<u32>() + wrapped;
+    println!();
+    println!("total cards: {}", total);
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        let numbers = line.split_once(": ").and_then(|(_, numbers)| numbers.split_once(" | "));
+        let Some((winning_nums, our_nums)) = numbers else {
+            let message = format!("expected 'Card <id>: <winning numbers> | <numbers>', got '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        for n in winning_nums.split_ascii_whitespace().chain(our_nums.split_ascii_whitespace()) {
+            if n.parse::<u32>().is_err() {
+                diagnostics.push(Diagnostic::error(format!("invalid number '{}'", n)).on_line(y));
+            }
+        }
+    }
+
+    if diagnostics.is_empty() {
+        let cards = input.lines().count();
+        for (y, card) in input.lines().enumerate() {
+            let past = (y + 1 + card_matches(card)).saturating_sub(cards);
+            if past > 0 {
+                let message = format!("wins copies of {} card(s) past the last card", past);
+                let diagnostic = match params().overflow {
+                    Overflow::Error => Diagnostic::error(message),
+                    _ => Diagnostic::warning(message),
+                };
+                diagnostics.push(diagnostic.on_line(y));
+            }
         }
     }
-    cards.iter().map(|(c, _)| c).sum()
+    diagnostics
 }
 
-aoc::main!();
+aoc::main! {
+    params {
+        overflow: Overflow = Overflow::Clamp,
+    }
+    check: validate,
+    commands {
+        stats: stats,
+    }
+}
@@ -1,4 +1,5 @@
 use std::ops::Range;
+use aoc::Diagnostic;
 
 fn parse_seed_nums(input: &str) -> impl Iterator<Item=u64> + '_ {
     input.lines()
@@ -10,83 +11,352 @@ fn parse_seed_nums(input: &str) -> impl Iterator<Item=u64> + '_ {
         .map(|n| n.parse().unwrap())
 }
 
-fn parse_map_groups(input: &str) -> Vec<Vec<(u64, u64, u64)>> {
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    line.strip_suffix(" map:")?.split_once("-to-")
+}
+
+fn parse_map(line: &str) -> Option<(u64, u64, u64)> {
+    let mut nums = line.split_ascii_whitespace().map(|n| n.parse().ok());
+    let map = (nums.next()??, nums.next()??, nums.next()??);
+    nums.next().is_none().then_some(map)
+}
+
+struct MapGroup<'a> {
+    from: &'a str,
+    to: &'a str,
+    maps: Vec<(u64, u64, u64)>,
+}
+
+fn parse_map_groups(input: &str) -> Vec<MapGroup<'_>> {
     input.split("\n\n")
         .skip(1)
         .map(|maps| {
-            maps.lines()
-                .skip(1)
-                .map(|map| {
-                    let mut nums = map.split_ascii_whitespace()
-                        .map(|n| n.parse().unwrap());
-                    let dest = nums.next().unwrap();
-                    let src = nums.next().unwrap();
-                    let len = nums.next().unwrap();
-                    (dest, src, len)
-                })
-                .collect()
+            let mut lines = maps.lines();
+            let (from, to) = parse_header(lines.next().unwrap()).unwrap();
+            let maps = lines.map(|map| parse_map(map).unwrap()).collect();
+            MapGroup { from, to, maps }
         })
         .collect()
 }
 
-fn part_1(input: String) -> u64 {
-    let mut nums = parse_seed_nums(&input).collect::<Vec<_>>();
-    let map_groups = parse_map_groups(&input);
-    for map_group in &map_groups {
-        for n in &mut nums {
-            for &(dest, src, len) in map_group {
-                if (src..src + len).contains(n) {
-                    *n = *n - src + dest;
-                    break;
-                }
+// a piecewise-linear map over all of u64, as sorted (segment start, offset) pairs
+struct Mapping {
+    segments: Vec<(u64, i64)>,
+}
+
+impl Mapping {
+    fn from_group(map_group: &[(u64, u64, u64)]) -> Self {
+        let mut bounds = vec![0];
+        for &(_, src, len) in map_group {
+            bounds.push(src);
+            bounds.push(src + len);
+        }
+        bounds.sort_unstable();
+        bounds.dedup();
+        let segments = bounds.into_iter()
+            .map(|start| {
+                // every map starts and ends on a bound, so the first map covering
+                // a segment's start covers the whole segment
+                let offset = map_group.iter()
+                    .find(|&&(_, src, len)| (src..src + len).contains(&start))
+                    .map_or(0, |&(dest, src, _)| dest as i64 - src as i64);
+                (start, offset)
+            })
+            .collect();
+        Self::normalised(segments)
+    }
+
+    fn normalised(mut segments: Vec<(u64, i64)>) -> Self {
+        segments.dedup_by_key(|&mut (_, offset)| offset);
+        Self { segments }
+    }
+
+    fn segment(&self, index: usize) -> (Range<u64>, i64) {
+        let (start, offset) = self.segments[index];
+        let end = self.segments.get(index + 1).map_or(u64::MAX, |&(end, _)| end);
+        (start..end, offset)
+    }
+
+    fn segment_index(&self, n: u64) -> usize {
+        self.segments.partition_point(|&(start, _)| start <= n) - 1
+    }
+
+    fn map(&self, n: u64) -> u64 {
+        let (_, offset) = self.segments[self.segment_index(n)];
+        n.checked_add_signed(offset).unwrap()
+    }
+
+    // calls visit with each piece of the range and that piece's offset
+    fn split(&self, range: Range<u64>, mut visit: impl FnMut(Range<u64>, i64)) {
+        let mut index = self.segment_index(range.start);
+        while index < self.segments.len() {
+            let (segment, offset) = self.segment(index);
+            if segment.start >= range.end {
+                break;
             }
+            visit(segment.start.max(range.start)..segment.end.min(range.end), offset);
+            index += 1;
         }
     }
-    nums.into_iter().min().unwrap()
-}
-
-fn remap_range(map_group: &[(u64, u64, u64)], range: Range<u64>, remapped: &mut Vec<Range<u64>>) {
-    let mut unmapped = vec![range];
-    for &(dest, src, len) in map_group {
-        let mut next = Vec::new();
-        for range in unmapped {
-            let overlap_start = range.start.max(src);
-            let overlap_end = range.end.min(src + len);
-            if overlap_start < overlap_end {
-                let remapped_start = overlap_start - src + dest;
-                let remapped_end = overlap_end - src + dest;
-                remapped.push(remapped_start..remapped_end);
-                if range.start < overlap_start {
-                    next.push(range.start..overlap_start);
-                }
-                if overlap_end < range.end {
-                    next.push(overlap_end..range.end);
+
+    fn map_range(&self, range: Range<u64>, mapped: &mut Vec<Range<u64>>) {
+        self.split(range, |piece, offset| {
+            let start = piece.start.checked_add_signed(offset).unwrap();
+            let end = piece.end.checked_add_signed(offset).unwrap();
+            mapped.push(start..end);
+        });
+    }
+
+    fn then(&self, next: &Mapping) -> Mapping {
+        let mut segments = Vec::new();
+        for index in 0..self.segments.len() {
+            let (segment, offset) = self.segment(index);
+            // the last segment runs to u64::MAX, so only unshifted segments are split as-is
+            if offset == 0 {
+                next.split(segment, |piece, next_offset| segments.push((piece.start, next_offset)));
+                continue;
+            }
+            let start = segment.start.checked_add_signed(offset).unwrap();
+            let end = segment.end.checked_add_signed(offset).unwrap();
+            next.split(start..end, |piece, next_offset| {
+                let source = piece.start.checked_add_signed(-offset).unwrap();
+                segments.push((source, offset + next_offset));
+            });
+        }
+        Self::normalised(segments)
+    }
+
+    fn inverse(&self) -> Inverse {
+        let mut images = (0..self.segments.len())
+            .map(|index| {
+                let (segment, offset) = self.segment(index);
+                let start = segment.start.checked_add_signed(offset).unwrap();
+                let end = match offset {
+                    0 => segment.end,
+                    _ => segment.end.checked_add_signed(offset).unwrap(),
+                };
+                (start..end, offset)
+            })
+            .collect::<Vec<_>>();
+        images.sort_unstable_by_key(|(image, _)| image.start);
+        Inverse { images }
+    }
+}
+
+// images of a mapping's segments, sorted by start; they may overlap when the
+// mapping isn't injective, so a point can have several preimages
+struct Inverse {
+    images: Vec<(Range<u64>, i64)>,
+}
+
+impl Inverse {
+    fn preimage(&self, range: Range<u64>, preimage: &mut Vec<Range<u64>>) {
+        let candidates = self.images.partition_point(|(image, _)| image.start < range.end);
+        for (image, offset) in &self.images[..candidates] {
+            let start = image.start.max(range.start);
+            let end = image.end.min(range.end);
+            if start < end {
+                let source_start = start.checked_add_signed(-offset).unwrap();
+                let source_end = end.checked_add_signed(-offset).unwrap();
+                preimage.push(source_start..source_end);
+            }
+        }
+    }
+
+    // scans locations upwards, returning the first one with a preimage in a seed range
+    fn lowest(&self, seeds: &[Range<u64>]) -> Option<u64> {
+        let mut lowest = None::<u64>;
+        for (image, offset) in &self.images {
+            if lowest.is_some_and(|lowest| image.start >= lowest) {
+                break;
+            }
+            let source_start = image.start.checked_add_signed(-offset).unwrap();
+            let source_end = image.end.checked_add_signed(-offset).unwrap();
+            for seed in seeds {
+                let start = seed.start.max(source_start);
+                if start < seed.end.min(source_end) {
+                    let location = start.checked_add_signed(*offset).unwrap();
+                    lowest = Some(lowest.map_or(location, |lowest| lowest.min(location)));
                 }
-            } else {
-                next.push(range);
             }
         }
-        unmapped = next;
+        lowest
     }
-    remapped.append(&mut unmapped);
 }
 
-fn part_2(input: String) -> u64 {
+impl std::fmt::Display for Mapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:>20}  {:>20}  {:>12}", "start", "end", "offset")?;
+        for index in 0..self.segments.len() {
+            let (segment, offset) = self.segment(index);
+            writeln!(f, "{:>20}  {:>20}  {:>+12}", segment.start, segment.end, offset)?;
+        }
+        Ok(())
+    }
+}
+
+// composes the groups leading from one category to another, if there's such a chain
+fn chain(map_groups: &[MapGroup], from: &str, to: &str) -> Option<Mapping> {
+    let mut mapping = Mapping { segments: vec![(0, 0)] };
+    let mut category = from;
+    // a chain longer than the number of groups must have looped
+    for _ in 0..map_groups.len() {
+        if category == to {
+            break;
+        }
+        let group = map_groups.iter().find(|g| g.from == category)?;
+        mapping = mapping.then(&Mapping::from_group(&group.maps));
+        category = group.to;
+    }
+    (category == to).then_some(mapping)
+}
+
+fn seed_to_location(input: &str) -> Mapping {
+    chain(&parse_map_groups(input), "seed", "location").unwrap()
+}
+
+fn part_1(input: String) -> u64 {
+    let mapping = seed_to_location(&input);
+    parse_seed_nums(&input).map(|n| mapping.map(n)).min().unwrap()
+}
+
+fn parse_seed_ranges(input: &str) -> Vec<Range<u64>> {
     let mut ranges = Vec::new();
-    let mut seeds = parse_seed_nums(&input);
+    let mut seeds = parse_seed_nums(input);
     while let (Some(start), Some(len)) = (seeds.next(), seeds.next()) {
         ranges.push(start..start + len);
     }
+    ranges
+}
+
+fn part_2(input: String) -> u64 {
+    let ranges = parse_seed_ranges(&input);
+    let mapping = seed_to_location(&input);
+    let mut locations = Vec::new();
+    for range in ranges {
+        mapping.map_range(range, &mut locations);
+    }
+    locations.iter().map(|r| r.start).min().unwrap()
+}
+
+fn table(_: &aoc::Args) {
+    print!("{}", seed_to_location(&aoc::stdin::<String>()));
+}
+
+fn seeds(args: &aoc::Args) {
+    let start = args.positional(1).and_then(|n| n.parse().ok()).unwrap_or_else(|| {
+        aoc::fail("Expected a location, or a location range as <start> <end>")
+    });
+    let end = args.positional_or(2, start + 1);
+    let inverse = seed_to_location(&aoc::stdin::<String>()).inverse();
+    let mut preimage = Vec::new();
+    inverse.preimage(start..end, &mut preimage);
+    preimage.sort_unstable_by_key(|r| r.start);
+    for range in preimage {
+        println!("{}..{}", range.start, range.end);
+    }
+}
+
+fn lowest(_: &aoc::Args) {
+    let input = aoc::stdin::<String>();
+    let inverse = seed_to_location(&input).inverse();
+    match inverse.lowest(&parse_seed_ranges(&input)) {
+        Some(location) => println!("{}", location),
+        None => println!("no seeds"),
+    }
+}
 
+fn convert(args: &aoc::Args) {
+    let (Some(from), Some(to)) = (args.positional(1), args.positional(2)) else {
+        aoc::fail("Expected <from category> <to category> <numbers...>");
+    };
+    let input = aoc::stdin::<String>();
     let map_groups = parse_map_groups(&input);
-    for map_group in &map_groups {
-        let mut remapped = Vec::new();
-        for range in ranges {
-            remap_range(map_group, range, &mut remapped);
+    let nums = (3..).map_while(|i| args.positional(i)).map(|n| {
+        n.parse::<u64>().unwrap_or_else(|e| aoc::fail(format!("Invalid number '{}': {}", n, e)))
+    });
+    if let Some(mapping) = chain(&map_groups, from, to) {
+        for n in nums {
+            println!("{} {} -> {} {}", from, n, to, mapping.map(n));
+        }
+    } else if let Some(mapping) = chain(&map_groups, to, from) {
+        let inverse = mapping.inverse();
+        for n in nums {
+            let mut preimage = Vec::new();
+            inverse.preimage(n..n + 1, &mut preimage);
+            let mut preimage = preimage.into_iter().map(|r| r.start).collect::<Vec<_>>();
+            preimage.sort_unstable();
+            println!("{} {} -> {} {:?}", from, n, to, preimage);
         }
-        ranges = remapped;
+    } else {
+        aoc::fail(format!("No chain of maps between '{}' and '{}'", from, to));
     }
-    ranges.iter().map(|r| r.start).min().unwrap()
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut lines = input.lines().enumerate();
+    match lines.next() {
+        Some((_, line)) if line.strip_prefix("seeds: ").is_some_and(|seeds| {
+            seeds.split_ascii_whitespace().all(|n| n.parse::<u64>().is_ok())
+        }) => {}
+        _ => diagnostics.push(Diagnostic::error("expected 'seeds: ' and a list of numbers").on_line(0)),
+    }
+
+    let mut groups = Vec::<(usize, &str, &str, Vec<(usize, u64, u64)>)>::new();
+    for (y, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some((from, to)) = parse_header(line) {
+            if let Some((first, ..)) = groups.iter().find(|&&(_, f, ..)| f == from) {
+                let message = format!("'{}' is mapped from more than once (first on line {})", from, first + 1);
+                diagnostics.push(Diagnostic::error(message).on_line(y));
+            }
+            groups.push((y, from, to, Vec::new()));
+            continue;
+        }
+        let Some((_, src, len)) = parse_map(line) else {
+            let message = format!("expected 'X-to-Y map:' or 'dest src len', got '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        let Some((.., maps)) = groups.last_mut() else {
+            diagnostics.push(Diagnostic::error("map before any 'X-to-Y map:' header").on_line(y));
+            continue;
+        };
+        for &(other_y, other_src, other_len) in maps.iter() {
+            if src.max(other_src) < (src + len).min(other_src + other_len) {
+                let message = format!("source range overlaps line {}, which takes precedence", other_y + 1);
+                diagnostics.push(Diagnostic::warning(message).on_line(y));
+            }
+        }
+        maps.push((y, src, len));
+    }
+
+    let mut category = "seed";
+    for _ in 0..groups.len() {
+        if category == "location" {
+            break;
+        }
+        let Some(&(_, _, to, _)) = groups.iter().find(|&&(_, from, ..)| from == category) else {
+            break;
+        };
+        category = to;
+    }
+    if category != "location" {
+        let message = format!("maps from 'seed' stop at '{}' without reaching 'location'", category);
+        diagnostics.push(Diagnostic::error(message));
+    }
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+    commands {
+        convert: convert,
+        table: table,
+        seeds: seeds,
+        lowest: lowest,
+    }
+}
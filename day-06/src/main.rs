@@ -1,19 +1,243 @@
-fn ways_to_beat_record(time: u64, record: u64) -> usize {
-    (1..time).filter(|held| held * (time - held) > record).count()
+use std::str::FromStr;
+use aoc::Diagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Physics {
+    // speed gained per ms held
+    rate: u64,
+    // top speed, if any
+    cap: Option<u64>,
+    // speed lost per ms travelled
+    drag: u64,
+}
+
+impl Physics {
+    const CLASSIC: Physics = Physics { rate: 1, cap: None, drag: 0 };
+
+    fn distance(&self, time: u64, held: u64) -> u128 {
+        let speed = (self.rate as u128 * held as u128).min(self.cap.map_or(u128::MAX, u128::from));
+        let travel = (time - held) as u128;
+        if self.drag == 0 {
+            return speed * travel;
+        }
+        // the boat stops once drag has taken all its speed
+        let drag = self.drag as u128;
+        let moving = travel.min(speed.div_ceil(drag));
+        moving * speed - drag * moving * moving.saturating_sub(1) / 2
+    }
 }
 
-fn part_1(input: String) -> usize {
+impl FromStr for Physics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut physics = Self::CLASSIC;
+        for setting in s.split(',').filter(|s| !s.is_empty()) {
+            let (name, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=value, got '{}'", setting))?;
+            let value = value.parse().map_err(|e| format!("invalid {} '{}': {}", name, value, e))?;
+            match name {
+                "rate" => physics.rate = value,
+                "cap" => physics.cap = Some(value),
+                "drag" => physics.drag = value,
+                _ => return Err(format!("expected rate, cap or drag, got '{}'", name)),
+            }
+        }
+        Ok(physics)
+    }
+}
+
+// any distance function that rises then falls over the hold times
+fn ways_to_beat_record_search(physics: &Physics, time: u64, record: u64) -> u64 {
+    let record = record as u128;
+    let distance = |held| physics.distance(time, held);
+
+    // first hold time no better than the one after it
+    let (mut low, mut high) = (0, time);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if distance(mid) < distance(mid + 1) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    let peak = low;
+    if distance(peak) <= record {
+        return 0;
+    }
+
+    // first winning hold time at or before the peak
+    let (mut low, mut high) = (0, peak);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if distance(mid) > record {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    let first = low;
+
+    // last winning hold time at or after the peak
+    let (mut low, mut high) = (peak, time);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if distance(mid) > record {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low - first + 1
+}
+
+#[cfg(test)]
+fn ways_to_beat_record_naive(time: u64, record: u64) -> u64 {
+    (1..time).filter(|held| held * (time - held) > record).count() as u64
+}
+
+fn ways_to_beat_record(time: u64, record: u64) -> u64 {
+    // winning holds h satisfy h^2 - time * h + record < 0, so lie strictly between
+    // the roots (time -/+ sqrt(time^2 - 4 * record)) / 2
+    let (time, record) = (time as u128, record as u128);
+    let Some(discriminant) = (time * time).checked_sub(4 * record) else {
+        return 0;
+    };
+    // the isqrt floor can leave the candidate on or below the lower root, never above it
+    let mut lowest = (time - discriminant.isqrt()) / 2;
+    while lowest <= time / 2 && lowest * (time - lowest) <= record {
+        lowest += 1;
+    }
+    if lowest > time / 2 {
+        return 0;
+    }
+    // the winning holds are symmetric about time / 2
+    (time - 2 * lowest + 1) as u64
+}
+
+fn ways_to_win(time: u64, record: u64) -> u64 {
+    match params().physics {
+        Physics::CLASSIC => ways_to_beat_record(time, record),
+        physics => ways_to_beat_record_search(&physics, time, record),
+    }
+}
+
+fn part_1(input: String) -> u64 {
     let (times, records) = input.trim_end().split_once('\n').unwrap();
     let times = times.split_ascii_whitespace().skip(1).map(|n| n.parse().unwrap());
     let records = records.split_ascii_whitespace().skip(1).map(|n| n.parse().unwrap());
-    times.zip(records).map(|(t, r)| ways_to_beat_record(t, r)).product()
+    times.zip(records).map(|(t, r)| ways_to_win(t, r)).product()
 }
 
-fn part_2(input: String) -> usize {
+fn part_2(input: String) -> u64 {
     let (time, record) = input.trim_end().split_once('\n').unwrap();
     let time = time.strip_prefix("Time:").unwrap().replace(' ', "").parse().unwrap();
     let record = record.strip_prefix("Distance:").unwrap().replace(' ', "").parse().unwrap();
-    ways_to_beat_record(time, record)
+    ways_to_win(time, record)
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let lines = input.lines().collect::<Vec<_>>();
+    let [times, records] = lines.as_slice() else {
+        diagnostics.push(Diagnostic::error("expected a 'Time:' line and a 'Distance:' line"));
+        return diagnostics;
+    };
+
+    let mut counts = Vec::new();
+    for (y, line, prefix) in [(0, times, "Time:"), (1, records, "Distance:")] {
+        let Some(nums) = line.strip_prefix(prefix) else {
+            diagnostics.push(Diagnostic::error(format!("expected '{}'", prefix)).on_line(y));
+            continue;
+        };
+        for n in nums.split_ascii_whitespace() {
+            if n.parse::<u64>().is_err() {
+                diagnostics.push(Diagnostic::error(format!("invalid number '{}'", n)).on_line(y));
+            }
+        }
+        if nums.replace(' ', "").parse::<u64>().is_err() {
+            diagnostics.push(Diagnostic::error("the numbers don't join into one for part 2").on_line(y));
+        }
+        counts.push(nums.split_ascii_whitespace().count());
+    }
+    if let [times, records] = counts[..] {
+        if times != records {
+            let message = format!("{} times but {} records", times, records);
+            diagnostics.push(Diagnostic::error(message));
+        }
+    }
+    diagnostics
+}
+
+aoc::main! {
+    params {
+        physics: Physics = Physics::CLASSIC,
+    }
+    check: validate,
 }
 
-aoc::main!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_roots() {
+        // holding 3 or 7 exactly ties the record, so neither wins
+        assert_eq!(ways_to_beat_record(10, 21), 3);
+        assert_eq!(ways_to_beat_record(30, 200), 9);
+        // the best hold exactly ties the record
+        assert_eq!(ways_to_beat_record(10, 25), 0);
+        assert_eq!(ways_to_beat_record(10, 24), 1);
+    }
+
+    #[test]
+    fn no_time() {
+        assert_eq!(ways_to_beat_record(0, 0), 0);
+        assert_eq!(ways_to_beat_record(0, 1), 0);
+        assert_eq!(ways_to_beat_record_search(&Physics::CLASSIC, 0, 0), 0);
+    }
+
+    #[test]
+    fn long_race() {
+        assert_eq!(ways_to_beat_record(1_000_000_000_000_000, 1), 999_999_999_999_999);
+    }
+
+    #[test]
+    fn matches_iterative_count() {
+        for time in 0..=100 {
+            // records past the best distance have no winners, so check one past it
+            for record in 0..=time * time / 4 + 1 {
+                let naive = ways_to_beat_record_naive(time, record);
+                assert_eq!(ways_to_beat_record(time, record), naive, "time {}, record {}", time, record);
+                let search = ways_to_beat_record_search(&Physics::CLASSIC, time, record);
+                assert_eq!(search, naive, "time {}, record {}", time, record);
+            }
+        }
+    }
+
+    #[test]
+    fn search_matches_direct_count() {
+        let physics = [
+            Physics::CLASSIC,
+            Physics { rate: 3, cap: None, drag: 0 },
+            Physics { rate: 1, cap: Some(5), drag: 0 },
+            Physics { rate: 2, cap: Some(7), drag: 0 },
+            Physics { rate: 1, cap: None, drag: 1 },
+            Physics { rate: 4, cap: None, drag: 3 },
+            Physics { rate: 2, cap: Some(9), drag: 2 },
+            Physics { rate: 1, cap: Some(0), drag: 0 },
+        ];
+        for physics in &physics {
+            for time in 0..=60 {
+                let best = (0..=time).map(|held| physics.distance(time, held)).max().unwrap() as u64;
+                for record in 0..=best + 1 {
+                    let direct = (0..=time).filter(|&held| physics.distance(time, held) > record as u128).count() as u64;
+                    let search = ways_to_beat_record_search(physics, time, record);
+                    assert_eq!(search, direct, "{:?}, time {}, record {}", physics, time, record);
+                }
+            }
+        }
+    }
+}
@@ -1,58 +1,204 @@
-fn hand_rank(hand: &str, enable_jokers: bool) -> (u32, [usize; 5]) {
-    let card_order = match enable_jokers {
-        false => "AKQJT987865432",
-        true => "AKQT987865432J",
-    };
-    let card_rank = |card| card_order.find(card).unwrap();
-
-    let mut card_counts = [0; 14];
-    let mut card_ranks = [0; 5];
-    for (index, card) in hand.chars().enumerate() {
-        let rank = card_rank(card);
-        card_counts[rank] += 1;
-        card_ranks[index] = rank;
-    }
-
-    if enable_jokers {
-        let joker_index = card_rank('J');
-        let jokers = card_counts[joker_index];
-        card_counts[joker_index] = 0;
-        card_counts.sort_unstable();
-        *card_counts.last_mut().unwrap() += jokers;
-    } else {
-        card_counts.sort_unstable();
-    }
-    let rank = match card_counts {
-        [.., 5] => 0,
-        [.., 1, 4] => 1,
-        [.., 2, 3] => 2,
-        [.., 1, 1, 3] => 3,
-        [.., 1, 2, 2] => 4,
-        [.., 1, 1, 1, 2] => 5,
-        [.., 1, 1, 1, 1, 1] => 6,
-        _ => unreachable!(),
-    };
+use std::str::FromStr;
+use aoc::Diagnostic;
+
+// strongest first
+struct Deck(Vec<char>);
+
+impl FromStr for Deck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cards = Vec::new();
+        for card in s.chars() {
+            if card.is_whitespace() {
+                return Err("cards can't be whitespace".to_owned());
+            }
+            if cards.contains(&card) {
+                return Err(format!("card '{}' appears more than once", card));
+            }
+            cards.push(card);
+        }
+        if cards.is_empty() {
+            return Err("deck has no cards".to_owned());
+        }
+        Ok(Self(cards))
+    }
+}
+
+struct CardRules {
+    // weakest first, with the wildcards weakest of all
+    order: Vec<char>,
+    wildcards: Vec<char>,
+    hand_size: usize,
+}
+
+impl CardRules {
+    fn new(deck: &Deck, wildcards: &str, hand_size: usize) -> Result<Self, String> {
+        let wildcards = wildcards.chars().collect::<Vec<_>>();
+        if let Some(card) = wildcards.iter().find(|c| !deck.0.contains(c)) {
+            return Err(format!("wildcard '{}' isn't in the deck", card));
+        }
+        if let Some((_, card)) = wildcards.iter().enumerate().find(|(i, c)| wildcards[..*i].contains(c)) {
+            return Err(format!("wildcard '{}' appears more than once", card));
+        }
+        // wildcards are the weakest cards, keeping their relative order from the deck
+        let (mut order, rest): (Vec<_>, Vec<_>) = deck.0.iter().rev().partition(|c| wildcards.contains(c));
+        order.extend(rest);
+        Ok(Self { order, wildcards, hand_size })
+    }
 
-    (rank, card_ranks)
+    fn strength(&self, card: char) -> Result<usize, String> {
+        self.order.iter().position(|&c| c == card).ok_or_else(|| format!("unknown card '{}'", card))
+    }
+
+    // card counts, largest first, with wildcards joining the largest group;
+    // comparing these lexicographically orders the hand types for any hand size
+    fn signature(&self, hand: &str) -> Vec<usize> {
+        let mut signature = self.groups(hand).into_iter().map(|(_, n)| n).collect::<Vec<_>>();
+        let wild = hand.chars().filter(|c| self.wildcards.contains(c)).count();
+        match signature.first_mut() {
+            Some(largest) => *largest += wild,
+            None => signature.push(wild),
+        }
+        signature
+    }
+
+    // non-wild cards and their counts, largest group then strongest card first
+    fn groups(&self, hand: &str) -> Vec<(char, usize)> {
+        let mut groups = Vec::<(char, usize)>::new();
+        for card in hand.chars().filter(|c| !self.wildcards.contains(c)) {
+            match groups.iter_mut().find(|(c, _)| *c == card) {
+                Some((_, count)) => *count += 1,
+                None => groups.push((card, 1)),
+            }
+        }
+        groups.sort_unstable_by_key(|&(c, n)| std::cmp::Reverse((n, self.strength(c).unwrap())));
+        groups
+    }
+
+    fn hand_key(&self, hand: &str) -> Result<(Vec<usize>, Vec<usize>), String> {
+        let cards = hand.chars().count();
+        if cards != self.hand_size {
+            return Err(format!("hand '{}' has {} cards, expected {}", hand, cards, self.hand_size));
+        }
+        let strengths = hand.chars().map(|card| self.strength(card)).collect::<Result<_, _>>()?;
+        Ok((self.signature(hand), strengths))
+    }
+}
+
+fn hand_type(signature: &[usize]) -> String {
+    let name = match signature {
+        [5] => "five of a kind",
+        [4, 1] => "four of a kind",
+        [3, 2] => "full house",
+        [3, 1, 1] => "three of a kind",
+        [2, 2, 1] => "two pair",
+        [2, 1, 1, 1] => "one pair",
+        [1, 1, 1, 1, 1] => "high card",
+        _ => return signature.iter().map(|n| n.to_string()).collect::<Vec<_>>().join("+"),
+    };
+    name.to_owned()
 }
 
-fn total_winnings(plays: &str, enable_jokers: bool) -> u32 {
+type Play<'p> = ((Vec<usize>, Vec<usize>), &'p str, u32);
+
+// weakest hand first
+fn ranked_plays<'p>(plays: &'p str, rules: &CardRules) -> Vec<Play<'p>> {
     let mut plays = plays.lines()
-        .map(|play| {
-            let (hand, bid) = play.split_once(' ').unwrap();
-            (hand_rank(hand, enable_jokers), bid.parse::<u32>().unwrap())
+        .enumerate()
+        .map(|(y, play)| {
+            let parsed = play.split_once(' ').and_then(|(hand, bid)| Some((hand, bid.parse().ok()?)));
+            let Some((hand, bid)) = parsed else {
+                aoc::fail(format!("line {}: expected '<hand> <bid>', got '{}'", y + 1, play));
+            };
+            let key = rules.hand_key(hand).unwrap_or_else(|e| aoc::fail(format!("line {}: {}", y + 1, e)));
+            (key, hand, bid)
         })
         .collect::<Vec<_>>();
     plays.sort_unstable();
-    plays.iter().rev().zip(1..).map(|((_, bid), rank)| bid * rank).sum()
+    plays
+}
+
+fn total_winnings(plays: &str, rules: &CardRules) -> u32 {
+    let plays = ranked_plays(plays, rules);
+    if aoc::args().flag("explain") {
+        explain(&plays, rules);
+    }
+    plays.iter().zip(1..).map(|((_, _, bid), rank)| bid * rank).sum()
+}
+
+fn rules(enable_wildcards: bool) -> CardRules {
+    let wildcards = match enable_wildcards {
+        false => "",
+        true => &params().wildcards,
+    };
+    CardRules::new(&params().deck, wildcards, params().hand_size).unwrap_or_else(|e| aoc::fail(e))
 }
 
 fn part_1(input: String) -> u32 {
-    total_winnings(&input, false)
+    total_winnings(&input, &rules(false))
 }
 
 fn part_2(input: String) -> u32 {
-    total_winnings(&input, true)
+    total_winnings(&input, &rules(true))
 }
 
-aoc::main!();
+fn explain(plays: &[Play], rules: &CardRules) {
+    println!("rank  hand      type             wildcards     bid  winnings");
+    for (((signature, _), hand, bid), rank) in plays.iter().zip(1..) {
+        let wild = hand.chars().filter(|c| rules.wildcards.contains(c)).count();
+        let wildcards = match (wild, rules.groups(hand).first()) {
+            (0, _) => "-".to_owned(),
+            (_, Some(&(card, _))) => format!("{} as {}", wild, card),
+            (_, None) => format!("{} as any", wild),
+        };
+        println!(
+            "{:4}  {:8}  {:15}  {:9}  {:6}  {:8}",
+            rank, hand, hand_type(signature), wildcards, bid, bid * rank,
+        );
+    }
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let rules = rules(true);
+    let mut diagnostics = Vec::new();
+    for (y, play) in input.lines().enumerate() {
+        let parsed = play.split_once(' ').and_then(|(hand, bid)| Some((hand, bid.parse::<u32>().ok()?)));
+        let Some((hand, _)) = parsed else {
+            let message = format!("expected '<hand> <bid>', got '{}'", play);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        if let Err(e) = rules.hand_key(hand) {
+            diagnostics.push(Diagnostic::error(e).on_line(y));
+        }
+    }
+    diagnostics
+}
+
+aoc::main! {
+    params {
+        deck: Deck = "AKQJT98765432".parse().unwrap(),
+        wildcards: String = "J".to_owned(),
+        hand_size: usize = 5,
+    }
+    check: validate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking<'p>(plays: &'p str, wildcards: &str) -> Vec<&'p str> {
+        let rules = CardRules::new(&"AKQJT98765432".parse().unwrap(), wildcards, 5).unwrap();
+        ranked_plays(plays, &rules).into_iter().map(|(_, hand, _)| hand).collect()
+    }
+
+    #[test]
+    fn wildcard_order_is_irrelevant() {
+        let plays = "JT234 1\nTJ234 2\n2J3T4 3\nQJJQ2 4\nKTJJT 5";
+        assert_eq!(ranking(plays, "JT"), ranking(plays, "TJ"));
+        // T sits below J in the deck, so it stays the weaker wildcard
+        assert_eq!(ranking("JT234 1\nTJ234 2", "JT"), ["TJ234", "JT234"]);
+    }
+}
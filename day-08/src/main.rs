@@ -1,16 +1,21 @@
 use std::collections::HashMap;
+use aoc::{Args, Diagnostic};
 
-fn parse_node(node: &str) -> (&str, (&str, &str)) {
-    let (name, children) = node.split_once(" = ").unwrap();
-    let (left, right) = children.split_once(", ").unwrap();
-    let left = left.strip_prefix("(").unwrap();
-    let right = right.strip_suffix(")").unwrap();
-    (name, (left, right))
+fn parse_node(node: &str) -> Option<(&str, (&str, &str))> {
+    let (name, children) = node.split_once(" = ")?;
+    let (left, right) = children.split_once(", ")?;
+    let left = left.strip_prefix("(")?;
+    let right = right.strip_suffix(")")?;
+    Some((name, (left, right)))
 }
 
-fn part_1(input: String) -> usize {
+fn parse_input(input: &str) -> (&str, HashMap<&str, (&str, &str)>) {
     let (path, graph) = input.split_once("\n\n").unwrap();
-    let graph = graph.lines().map(parse_node).collect::<HashMap<_, _>>();
+    (path, graph.lines().map(|n| parse_node(n).unwrap()).collect())
+}
+
+fn part_1(input: String) -> usize {
+    let (path, graph) = parse_input(&input);
 
     let mut current = "AAA";
     for (steps, direction) in path.chars().cycle().enumerate() {
@@ -49,67 +54,273 @@ fn visited<'g>(graph: &'g HashMap<&str, (&str, &str)>, path: &str, start: &'g st
     }
 }
 
-//TODO this is not strictly correct; it skips all nodes before the start of the cycle.
-// Z-nodes tend to exist within the cycle with overwhelming probability, so this works out,
-// but it's not technically correct.
-struct ZNodeIter {
-    indices: Vec<usize>,
-    index: usize,
-    cycle_size: usize,
+// the steps at which a ghost is on a Z-node: a finite set before its cycle starts,
+// then a set of residues that repeat every cycle
+#[derive(Debug, Clone)]
+struct Ghost {
+    cycle_start: usize,
+    cycle_len: usize,
+    prefix_hits: Vec<usize>,
+    residues: Vec<usize>,
 }
 
-impl ZNodeIter {
-    pub fn new(visited: &[&str], cycle_start: usize) -> Self {
-        let indices = visited.iter()
+impl Ghost {
+    fn new(visited: &[&str], cycle_start: usize) -> Self {
+        let hits = visited.iter()
             .enumerate()
-            .skip(cycle_start)
             .filter(|(_, n)| n.ends_with('Z'))
-            .map(|(i, _)| i)
-            .collect::<Vec<_>>();
-        let cycle_size = visited.len() - cycle_start;
-        Self { indices, cycle_size, index: 0 }
+            .map(|(i, _)| i);
+        let (prefix_hits, residues) = hits.partition(|&i| i < cycle_start);
+        let cycle_len = visited.len() - cycle_start;
+        Self { cycle_start, cycle_len, prefix_hits, residues }
+    }
+
+    fn hits(&self, step: usize) -> bool {
+        if step < self.cycle_start {
+            return self.prefix_hits.contains(&step);
+        }
+        let offset = (step - self.cycle_start) % self.cycle_len;
+        self.residues.contains(&(self.cycle_start + offset))
     }
 }
 
-impl Iterator for ZNodeIter {
-    type Item = usize;
+fn gcd(a: i128, b: i128) -> i128 {
+    match b {
+        0 => a.abs(),
+        _ => gcd(b, a % b),
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let index = self.index % self.indices.len();
-        let cycles = self.index / self.indices.len();
-        self.index += 1;
-        Some(self.indices[index] + cycles * self.cycle_size)
+// x and y with a * x + b * y = gcd(a, b)
+fn bezout(a: i128, b: i128) -> (i128, i128) {
+    match b {
+        0 => (a.signum(), 0),
+        _ => {
+            let (x, y) = bezout(b, a % b);
+            (y, x - a / b * y)
+        }
+    }
+}
+
+// combines x = a (mod m) and x = b (mod n), whose moduli needn't be coprime
+fn crt((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    let g = gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
     }
+    let (inverse, _) = bezout(m / g, n / g);
+    let k = ((b - a) / g * inverse).rem_euclid(n / g);
+    let lcm = m / g * n;
+    Some(((a + m * k).rem_euclid(lcm), lcm))
+}
+
+fn first_common_hit(ghosts: &[Ghost]) -> Option<usize> {
+    // before the last cycle starts, the ghost with that cycle only has its prefix hits
+    let latest = ghosts.iter().max_by_key(|g| g.cycle_start)?;
+    let early = latest.prefix_hits.iter().find(|&&step| ghosts.iter().all(|g| g.hits(step)));
+    if let Some(&step) = early {
+        return Some(step);
+    }
+
+    // after it, every ghost is periodic, so each choice of residues is a congruence
+    let mut congruences = vec![(0, 1)];
+    for ghost in ghosts {
+        let mut combined = Vec::new();
+        for &congruence in &congruences {
+            for &residue in &ghost.residues {
+                let ghost_congruence = (residue as i128, ghost.cycle_len as i128);
+                combined.extend(crt(congruence, ghost_congruence));
+            }
+        }
+        combined.sort_unstable();
+        combined.dedup();
+        congruences = combined;
+    }
+    let from = latest.cycle_start as i128;
+    congruences.into_iter()
+        .map(|(x, m)| x + ((from - x).max(0) + m - 1) / m * m)
+        .min()
+        .map(|step| step as usize)
 }
 
 fn part_2(input: String) -> usize {
-    let (path, graph) = input.split_once("\n\n").unwrap();
-    let graph = graph.lines().map(parse_node).collect::<HashMap<_, _>>();
+    let (path, graph) = parse_input(&input);
 
-    let mut z_node_iters = graph.keys()
+    let ghosts = graph.keys()
         .filter(|n| n.ends_with('A'))
         .map(|start| visited(&graph, path, start))
-        .map(|(visited, cycle_start)| ZNodeIter::new(&visited, cycle_start).peekable())
+        .map(|(visited, cycle_start)| Ghost::new(&visited, cycle_start))
         .collect::<Vec<_>>();
+    first_common_hit(&ghosts).unwrap_or_else(|| aoc::fail("The ghosts are never all on Z-nodes at once"))
+}
 
-    loop {
-        let mut all_equal = true;
-        let target = z_node_iters.iter_mut()
-            .map(|z| *z.peek().unwrap())
-            .max()
-            .unwrap();
-        for z_iter in &mut z_node_iters {
-            while *z_iter.peek().unwrap() < target {
-                z_iter.next();
-            }
-            if *z_iter.peek().unwrap() != target {
-                all_equal = false;
+fn dot(_: &Args) {
+    let input = aoc::stdin::<String>();
+    let (_, graph) = parse_input(&input);
+    let mut nodes = graph.iter().collect::<Vec<_>>();
+    nodes.sort_unstable();
+
+    println!("digraph network {{");
+    for (name, (left, right)) in nodes {
+        match name.chars().last() {
+            Some('A') => println!("    \"{}\" [style=filled, fillcolor=palegreen];", name),
+            Some('Z') => println!("    \"{}\" [style=filled, fillcolor=salmon];", name),
+            _ => {}
+        }
+        if left == right {
+            println!("    \"{}\" -> \"{}\" [label=\"L/R\"];", name, left);
+        } else {
+            println!("    \"{}\" -> \"{}\" [label=\"L\"];", name, left);
+            println!("    \"{}\" -> \"{}\" [label=\"R\"];", name, right);
+        }
+    }
+    println!("}}");
+}
+
+fn cycles(_: &Args) {
+    let input = aoc::stdin::<String>();
+    let (path, graph) = parse_input(&input);
+    let mut starts = graph.keys().filter(|n| n.ends_with('A')).collect::<Vec<_>>();
+    starts.sort_unstable();
+
+    // the LCM of the cycle lengths is only right if each ghost is on a Z-node
+    // exactly at every multiple of its cycle length
+    let mut lcm_applies = true;
+    println!("start  tail  cycle  Z-nodes before cycle  Z-node offsets in cycle");
+    for start in starts {
+        let (visited, cycle_start) = visited(&graph, path, start);
+        let ghost = Ghost::new(&visited, cycle_start);
+        let offsets = ghost.residues.iter().map(|r| r - ghost.cycle_start).collect::<Vec<_>>();
+        lcm_applies &= ghost.prefix_hits.is_empty()
+            && ghost.residues.len() == 1
+            && ghost.residues[0].is_multiple_of(ghost.cycle_len);
+        println!(
+            "{:5}  {:4}  {:5}  {:20}  {:?}",
+            start, ghost.cycle_start, ghost.cycle_len, format!("{:?}", ghost.prefix_hits), offsets,
+        );
+    }
+    match lcm_applies {
+        true => println!("the LCM shortcut applies"),
+        false => println!("the LCM shortcut doesn't apply"),
+    }
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some((path, nodes)) = input.split_once("\n\n") else {
+        diagnostics.push(Diagnostic::error("expected the path, a blank line, then the nodes"));
+        return diagnostics;
+    };
+    if path.is_empty() {
+        diagnostics.push(Diagnostic::error("empty path").on_line(0));
+    }
+    for (x, c) in path.chars().enumerate() {
+        if c != 'L' && c != 'R' {
+            diagnostics.push(Diagnostic::error(format!("unknown direction '{}'", c)).at(0, x));
+        }
+    }
+
+    let mut graph = HashMap::new();
+    for (y, line) in nodes.lines().enumerate().map(|(i, l)| (i + 2, l)) {
+        let Some((name, children)) = parse_node(line) else {
+            let message = format!("expected 'AAA = (BBB, CCC)', got '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        if graph.insert(name, (y, children)).is_some() {
+            let message = format!("node '{}' is defined more than once", name);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+    }
+    for (name, &(y, (left, right))) in &graph {
+        for child in [left, right] {
+            if !graph.contains_key(child) {
+                let message = format!("node '{}' leads to undefined node '{}'", name, child);
+                diagnostics.push(Diagnostic::error(message).on_line(y));
             }
         }
-        if all_equal {
-            return target;
+    }
+    for node in ["AAA", "ZZZ"] {
+        if !graph.contains_key(node) {
+            let message = format!("no '{}' node, so part 1 can't run", node);
+            diagnostics.push(Diagnostic::warning(message));
         }
     }
+    if !graph.keys().any(|n| n.ends_with('A')) {
+        diagnostics.push(Diagnostic::warning("no start nodes ending in 'A' for part 2"));
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+    commands {
+        dot: dot,
+        cycles: cycles,
+    }
 }
 
-aoc::main!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ghost(cycle_start: usize, cycle_len: usize, prefix_hits: &[usize], residues: &[usize]) -> Ghost {
+        Ghost { cycle_start, cycle_len, prefix_hits: prefix_hits.to_vec(), residues: residues.to_vec() }
+    }
+
+    fn first_common_hit_naive(ghosts: &[Ghost]) -> Option<usize> {
+        (0..1000).find(|&step| ghosts.iter().all(|g| g.hits(step)))
+    }
+
+    #[test]
+    fn non_coprime_crt() {
+        assert_eq!(crt((1, 4), (3, 6)), Some((9, 12)));
+        assert_eq!(crt((1, 4), (2, 6)), None);
+        assert_eq!(crt((0, 1), (5, 7)), Some((5, 7)));
+    }
+
+    #[test]
+    fn prefix_hit() {
+        // step 2 comes before the first ghost's cycle, which never hits an even step
+        let ghosts = [ghost(5, 3, &[2], &[6]), ghost(0, 2, &[], &[0])];
+        assert_eq!(first_common_hit(&ghosts), Some(2));
+    }
+
+    #[test]
+    fn multiple_residues() {
+        let ghosts = [ghost(0, 5, &[], &[1, 3]), ghost(0, 7, &[], &[4, 6])];
+        assert_eq!(first_common_hit(&ghosts), Some(6));
+    }
+
+    #[test]
+    fn non_coprime_cycles() {
+        let ghosts = [ghost(0, 4, &[], &[1]), ghost(0, 6, &[], &[3])];
+        assert_eq!(first_common_hit(&ghosts), Some(9));
+        let ghosts = [ghost(0, 4, &[], &[1]), ghost(0, 6, &[], &[2])];
+        assert_eq!(first_common_hit(&ghosts), None);
+    }
+
+    #[test]
+    fn lifted_past_latest_cycle_start() {
+        // both congruences hold at step 3, but the first ghost's cycle hasn't started
+        let ghosts = [ghost(10, 4, &[], &[11]), ghost(0, 2, &[], &[1])];
+        assert_eq!(first_common_hit(&ghosts), Some(11));
+    }
+
+    #[test]
+    fn matches_naive_search() {
+        let ghosts = [
+            ghost(0, 6, &[], &[2, 5]),
+            ghost(3, 4, &[1, 2], &[4, 6]),
+            ghost(7, 9, &[3, 5], &[8, 12, 15]),
+            ghost(2, 10, &[0], &[9]),
+        ];
+        for a in &ghosts {
+            for b in &ghosts {
+                let pair = [a.clone(), b.clone()];
+                assert_eq!(first_common_hit(&pair), first_common_hit_naive(&pair), "{:?}", pair);
+            }
+        }
+    }
+}
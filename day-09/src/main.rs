@@ -1,6 +1,7 @@
-fn extrapolated_sum(input: &str, backwards: bool) -> i32 {
-    let mut sum = 0;
-    for line in input.lines() {
+use aoc::{Diagnostic, Lines};
+
+fn extrapolated_sum(input: Lines, backwards: bool) -> i32 {
+    input.fold(0, |mut sum, line| {
         let mut nums = line.split_ascii_whitespace()
             .map(|n| n.parse::<i32>().unwrap())
             .collect::<Vec<_>>();
@@ -13,16 +14,33 @@ fn extrapolated_sum(input: &str, backwards: bool) -> i32 {
             }
             sum += nums.pop().unwrap();
         }
-    }
-    sum
+        sum
+    })
 }
 
-fn part_1(input: String) -> i32 {
-    extrapolated_sum(&input, false)
+fn part_1(input: Lines) -> i32 {
+    extrapolated_sum(input, false)
 }
 
-fn part_2(input: String) -> i32 {
-    extrapolated_sum(&input, true)
+fn part_2(input: Lines) -> i32 {
+    extrapolated_sum(input, true)
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            diagnostics.push(Diagnostic::error("empty history").on_line(y));
+        }
+        for n in line.split_ascii_whitespace() {
+            if n.parse::<i32>().is_err() {
+                diagnostics.push(Diagnostic::error(format!("invalid number '{}'", n)).on_line(y));
+            }
+        }
+    }
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+}
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use aoc::Diagnostic;
 
 type Cell = (i32, i32);
 type Tile = Option<[Cardinal; 2]>;
@@ -29,23 +30,27 @@ impl Cardinal {
     }
 }
 
+fn parse_tile(tile_char: char) -> Option<Tile> {
+    match tile_char {
+        '|' => Some(Some([Cardinal::N, Cardinal::S])),
+        '-' => Some(Some([Cardinal::E, Cardinal::W])),
+        'L' => Some(Some([Cardinal::N, Cardinal::E])),
+        'J' => Some(Some([Cardinal::N, Cardinal::W])),
+        '7' => Some(Some([Cardinal::S, Cardinal::W])),
+        'F' => Some(Some([Cardinal::S, Cardinal::E])),
+        '.' => Some(None),
+        'S' => Some(None),
+        _ => None,
+    }
+}
+
 fn parse_input(input: &str) -> (Grid, Cell) {
     let mut map = HashMap::new();
     let mut start = (0, 0);
     for (y, row) in input.lines().enumerate() {
         for (x, tile_char) in row.chars().enumerate() {
             let cell = (x as i32, y as i32);
-            let tile = match tile_char {
-                '|' => Some([Cardinal::N, Cardinal::S]),
-                '-' => Some([Cardinal::E, Cardinal::W]),
-                'L' => Some([Cardinal::N, Cardinal::E]),
-                'J' => Some([Cardinal::N, Cardinal::W]),
-                '7' => Some([Cardinal::S, Cardinal::W]),
-                'F' => Some([Cardinal::S, Cardinal::E]),
-                '.' => None,
-                'S' => None,
-                _ => panic!(),
-            };
+            let tile = parse_tile(tile_char).unwrap();
             map.insert(cell, tile);
             if tile_char == 'S' {
                 start = cell;
@@ -62,17 +67,17 @@ fn can_enter(grid: &Grid, cell: Cell, dir: Cardinal) -> bool {
     false
 }
 
+fn start_connections(grid: &Grid, start: Cell) -> Vec<Cardinal> {
+    [Cardinal::N, Cardinal::E, Cardinal::S, Cardinal::W]
+        .into_iter()
+        .filter(|&dir| can_enter(grid, dir.apply(start), dir))
+        .collect()
+}
+
 fn infer_start(grid: &mut Grid, start: Cell) {
-    let mut tile = [Cardinal::N; 2];
-    let mut index = 0;
-    for dir in [Cardinal::N, Cardinal::E, Cardinal::S, Cardinal::W] {
-        if can_enter(&grid, dir.apply(start), dir) {
-            tile[index] = dir;
-            index += 1;
-        }
-    }
-    assert_eq!(index, 2);
-    grid.insert(start, Some(tile));
+    let connections = start_connections(grid, start);
+    assert_eq!(connections.len(), 2);
+    grid.insert(start, Some([connections[0], connections[1]]));
 }
 
 fn loop_cells(grid: &Grid, start: Cell) -> Vec<Cell> {
@@ -82,7 +87,7 @@ fn loop_cells(grid: &Grid, start: Cell) -> Vec<Cell> {
         let tile = grid.get(&current).unwrap().unwrap();
         for dir in tile {
             let next = dir.apply(current);
-            if Some(&next) != cells.last() && can_enter(&grid, next, dir) {
+            if Some(&next) != cells.last() && can_enter(grid, next, dir) {
                 cells.push(current);
                 current = next;
                 break;
@@ -93,6 +98,39 @@ fn loop_cells(grid: &Grid, start: Cell) -> Vec<Cell> {
     cells
 }
 
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut starts = Vec::new();
+    for (y, row) in input.lines().enumerate() {
+        for (x, tile_char) in row.chars().enumerate() {
+            if parse_tile(tile_char).is_none() {
+                diagnostics.push(Diagnostic::error(format!("unknown tile '{}'", tile_char)).at(y, x));
+            }
+            if tile_char == 'S' {
+                starts.push((x, y));
+            }
+        }
+    }
+
+    match starts.as_slice() {
+        [] => diagnostics.push(Diagnostic::error("no start tile 'S'")),
+        &[(x, y)] if diagnostics.is_empty() => {
+            let (grid, start) = parse_input(input);
+            let connections = start_connections(&grid, start).len();
+            if connections != 2 {
+                let message = format!("start tile connects to {} pipes, expected 2", connections);
+                diagnostics.push(Diagnostic::error(message).at(y, x));
+            }
+        }
+        [_] => {}
+        _ => for &(x, y) in &starts {
+            let message = format!("multiple start tiles ({} in total)", starts.len());
+            diagnostics.push(Diagnostic::error(message).at(y, x));
+        }
+    }
+    diagnostics
+}
+
 fn part_1(input: String) -> usize {
     let (mut grid, start) = parse_input(&input);
     infer_start(&mut grid, start);
@@ -121,7 +159,7 @@ fn part_2(input: String) -> i32 {
                 return Some((x..=x, true));
             }
 
-            let (end_x, end) = (&mut pipes)
+            let (end_x, end) = pipes
                 .find(|(_, p)| !p.contains(&Cardinal::E))
                 .unwrap();
             let flips = end.contains(&Cardinal::N) != pipe.contains(&Cardinal::N);
@@ -140,4 +178,6 @@ fn part_2(input: String) -> i32 {
     enclosed
 }
 
-aoc::main!();
+aoc::main! {
+    check: validate,
+}
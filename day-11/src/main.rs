@@ -1,5 +1,6 @@
 use std::ops::Range;
 use std::collections::HashSet;
+use aoc::Diagnostic;
 
 fn parse_galaxies(input: &str) -> HashSet<(i32, i32)> {
     let mut galaxies = HashSet::new();
@@ -30,7 +31,7 @@ fn axis_distance(expansions: &[Range<i32>], x1: i32, x2: i32, expansion_factor:
 }
 
 fn galaxy_pair_distance_sum(input: &str, expansion_factor: i32) -> i64 {
-    let galaxies = parse_galaxies(&input);
+    let galaxies = parse_galaxies(input);
     let x_expansions = expansion_ranges(&galaxies, |&(x, _)| x);
     let y_expansions = expansion_ranges(&galaxies, |&(_, y)| y);
     let mut sum = 0;
@@ -52,7 +53,16 @@ fn part_1(input: String) -> i64 {
 }
 
 fn part_2(input: String) -> i64 {
-    galaxy_pair_distance_sum(&input, 1_000_000)
+    galaxy_pair_distance_sum(&input, params().expansion_factor)
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    aoc::check_grid(input, |c| c == '.' || c == '#')
+}
+
+aoc::main! {
+    params {
+        expansion_factor: i32 = 1_000_000,
+    }
+    check: validate,
+}
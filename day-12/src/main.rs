@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use aoc::{Diagnostic, Lines};
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 struct Block {
@@ -31,7 +32,7 @@ fn enumerate_placements<'a>(
     cache: &mut HashMap<(u32, &'a [u32]), u64>,
     f: &mut impl FnMut(&'a [u32]) -> u64
 ) -> u64 {
-    assert_eq!(placement & (1 << block.len) - 1, placement, "{}", block.len);
+    assert_eq!(placement & ((1 << block.len) - 1), placement, "{}", block.len);
 
     let mut start = u128::BITS - placement.leading_zeros();
     if start != 0 {
@@ -83,26 +84,57 @@ fn possibilities<'a>(blocks: &'a [Block], runs: &'a [u32], cache: &mut HashMap<(
     n
 }
 
-fn part_1(input: String) -> u64 {
-    let mut sum = 0;
-    for line in input.lines() {
+fn part_1(input: Lines) -> u64 {
+    input.fold(0, |sum, line| {
         let (springs, runs) = line.split_once(' ').unwrap();
         let blocks = parse_springs(springs);
         let runs = parse_runs(runs);
-        sum += possibilities(&blocks, &runs, &mut HashMap::new());
-    }
-    sum
+        sum + possibilities(&blocks, &runs, &mut HashMap::new())
+    })
 }
 
-fn part_2(input: String) -> u64 {
-    let mut sum = 0;
-    for line in input.lines() {
+fn part_2(input: Lines) -> u64 {
+    input.fold(0, |sum, line| {
         let (springs, runs) = line.split_once(' ').unwrap();
-        let blocks = parse_springs(&[springs; 5].join("?"));
-        let runs = parse_runs(runs).repeat(5);
-        sum += possibilities(&blocks, &runs, &mut HashMap::new());
+        let unfold = params().unfold;
+        let blocks = parse_springs(&vec![springs; unfold].join("?"));
+        let runs = parse_runs(runs).repeat(unfold);
+        sum + possibilities(&blocks, &runs, &mut HashMap::new())
+    })
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        let Some((springs, runs)) = line.split_once(' ') else {
+            let message = format!("expected '<springs> <runs>', got '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        for (x, c) in springs.chars().enumerate() {
+            if !".#?".contains(c) {
+                diagnostics.push(Diagnostic::error(format!("unknown spring '{}'", c)).at(y, x));
+            }
+        }
+        for run in runs.split(',') {
+            if run.parse::<u32>().is_err() {
+                diagnostics.push(Diagnostic::error(format!("invalid run '{}'", run)).on_line(y));
+            }
+        }
+        // blocks of unknown or damaged springs are bitsets in a u128
+        let unfolded = vec![springs; params().unfold].join("?");
+        let longest = unfolded.split('.').map(str::len).max().unwrap_or(0);
+        if longest >= u128::BITS as usize {
+            let message = format!("unfolded springs have a block of {} cells, the most is 127", longest);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
     }
-    sum
+    diagnostics
 }
 
-aoc::main!();
+aoc::main! {
+    params {
+        unfold: usize = 5,
+    }
+    check: validate,
+}
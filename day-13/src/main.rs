@@ -1,3 +1,5 @@
+use aoc::Diagnostic;
+
 struct Grid {
     grid: Vec<bool>,
     width: i32,
@@ -80,4 +82,31 @@ fn part_2(input: String) -> i32 {
     mirror_summary(&input, 1)
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut offset = 0;
+    for pattern in input.split("\n\n") {
+        let grid_diagnostics = aoc::check_grid(pattern, |c| c == '.' || c == '#');
+        if grid_diagnostics.is_empty() {
+            let grid = Grid::parse(pattern);
+            for (part, smudges) in [(1, 0), (2, 1)] {
+                let columns = mirror_point(smudges, grid.width, |x1, x2| grid.col_diffs(x1, x2));
+                let rows = mirror_point(smudges, grid.height, |y1, y2| grid.row_diffs(y1, y2));
+                if columns.is_none() && rows.is_none() {
+                    let message = format!("pattern has no line of reflection for part {}", part);
+                    diagnostics.push(Diagnostic::warning(message).on_line(offset));
+                }
+            }
+        }
+        for mut diagnostic in grid_diagnostics {
+            diagnostic.line = Some(diagnostic.line.map_or(offset, |y| offset + y));
+            diagnostics.push(diagnostic);
+        }
+        offset += pattern.lines().count() + 1;
+    }
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+}
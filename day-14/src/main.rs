@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use aoc::{Diagnostic, Repl};
 
 type Cell = (i32, i32);
 
@@ -162,6 +163,12 @@ fn map_rocks(map: &Grid<EndpointMap>, dir: Direction, rocks: &mut Vec<Cell>) {
     }
 }
 
+fn spin_cycle(map: &Grid<EndpointMap>, rocks: &mut Vec<Cell>) {
+    for dir in [Direction::N, Direction::W, Direction::S, Direction::E] {
+        map_rocks(map, dir, rocks);
+    }
+}
+
 fn apply_cycles(map: &Grid<EndpointMap>, cycles: usize, rocks: &mut Vec<Cell>) {
     let mut state_map = HashMap::new();
     let mut states = Vec::new();
@@ -174,10 +181,7 @@ fn apply_cycles(map: &Grid<EndpointMap>, cycles: usize, rocks: &mut Vec<Cell>) {
             break;
         }
         states.push(rocks.clone());
-
-        for dir in [Direction::N, Direction::W, Direction::S, Direction::E] {
-            map_rocks(&map, dir, rocks);
-        }
+        spin_cycle(map, rocks);
     }
 }
 
@@ -185,8 +189,80 @@ fn part_2(input: String) -> i32 {
     let platform = parse_input(&input);
     let map = make_endpoint_map(&platform);
     let mut rocks = get_round_rocks(&platform);
-    apply_cycles(&map, 1_000_000_000, &mut rocks);
+    apply_cycles(&map, params().cycles, &mut rocks);
     rocks.iter().map(|(_, y)| platform.height - y).sum()
 }
 
-aoc::main!();
+struct Platform {
+    platform: Grid<Tile>,
+    map: Grid<EndpointMap>,
+    rocks: Vec<Cell>,
+    cycles: usize,
+}
+
+impl Platform {
+    fn load(&self) -> i32 {
+        self.rocks.iter().map(|(_, y)| self.platform.height - y).sum()
+    }
+
+    fn render(&self) -> String {
+        let rocks = self.rocks.iter().collect::<HashSet<_>>();
+        let mut rendered = String::new();
+        for y in 0..self.platform.height {
+            for x in 0..self.platform.width {
+                rendered.push(match self.platform.get(x, y) {
+                    Tile::Square => '#',
+                    _ if rocks.contains(&(x, y)) => 'O',
+                    _ => '.',
+                });
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+}
+
+fn repl(input: &str) -> Repl<'_, Platform> {
+    Repl::new(input, |input| {
+        let platform = parse_input(input);
+        let map = make_endpoint_map(&platform);
+        let rocks = get_round_rocks(&platform);
+        Platform { platform, map, rocks, cycles: 0 }
+    })
+    .command("step", "[cycles]", |platform, args| {
+        let cycles = aoc::repl::arg_or(args, 0, 1)?;
+        apply_cycles(&platform.map, cycles, &mut platform.rocks);
+        platform.cycles += cycles;
+        println!("{} cycles so far; load is {}", platform.cycles, platform.load());
+        Ok(())
+    })
+    .command("tilt", "<N|E|S|W>", |platform, args| {
+        let dir = match args.first().copied() {
+            Some("N") => Direction::N,
+            Some("E") => Direction::E,
+            Some("S") => Direction::S,
+            Some("W") => Direction::W,
+            _ => return Err("expected a direction".to_owned()),
+        };
+        map_rocks(&platform.map, dir, &mut platform.rocks);
+        println!("load is {}", platform.load());
+        Ok(())
+    })
+    .command("show", "", |platform, _| {
+        print!("{}", platform.render());
+        println!("{} cycles so far; load is {}", platform.cycles, platform.load());
+        Ok(())
+    })
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    aoc::check_grid(input, |c| c == '.' || c == 'O' || c == '#')
+}
+
+aoc::main! {
+    params {
+        cycles: usize = 1_000_000_000,
+    }
+    check: validate,
+    repl: repl,
+}
@@ -1,3 +1,5 @@
+use aoc::Diagnostic;
+
 fn hash(s: &str) -> u8 {
     s.bytes().fold(0, |a, c| a.wrapping_add(c).wrapping_mul(17))
 }
@@ -33,4 +35,23 @@ fn part_2(input: String) -> u32 {
     power
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut column = 0;
+    for step in input.trim().split(',') {
+        let valid = match step.split_once('=') {
+            Some((label, focal_length)) => !label.is_empty() && focal_length.parse::<u32>().is_ok(),
+            None => step.strip_suffix('-').is_some_and(|label| !label.is_empty()),
+        };
+        if !valid {
+            let message = format!("expected 'label=<focal length>' or 'label-', got '{}'", step);
+            diagnostics.push(Diagnostic::error(message).at(0, column));
+        }
+        column += step.len() + 1;
+    }
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+}
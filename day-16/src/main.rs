@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use aoc::Diagnostic;
 
 fn parse_grid(grid: &str) -> HashMap<(i32, i32), char> {
     grid.lines()
@@ -84,4 +85,10 @@ fn part_2(input: String) -> usize {
     max
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    aoc::check_grid(input, |c| ".|-/\\".contains(c))
+}
+
+aoc::main! {
+    check: validate,
+}
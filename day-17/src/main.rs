@@ -1,5 +1,6 @@
 use std::collections::{HashMap, BinaryHeap};
 use std::cmp::Reverse;
+use aoc::{Diagnostic, Repl};
 
 type Cell = (i32, i32);
 type Node = (i32, i32, bool);
@@ -35,8 +36,8 @@ fn neighbours(grid: &Grid, min_shift: i32, max_shift: i32, (x, y, y_axis): Node,
     }
 }
 
-fn min_cost(grid: &Grid, min_shift: i32, max_shift: i32) -> u32 {
-    let mut costs = HashMap::<Node, u32>::new();
+fn node_costs(grid: &Grid, min_shift: i32, max_shift: i32) -> HashMap<Node, u32> {
+    let mut costs = HashMap::from([((0, 0, false), 0), ((0, 0, true), 0)]);
     let mut to_visit = BinaryHeap::new();
     to_visit.push((Reverse(0), (0, 0, false)));
     to_visit.push((Reverse(0), (0, 0, true)));
@@ -50,11 +51,18 @@ fn min_cost(grid: &Grid, min_shift: i32, max_shift: i32) -> u32 {
             }
         });
     }
+    costs
+}
+
+fn cell_cost(costs: &HashMap<Node, u32>, (x, y): Cell) -> Option<u32> {
+    let x_cost = costs.get(&(x, y, false));
+    let y_cost = costs.get(&(x, y, true));
+    x_cost.into_iter().chain(y_cost).min().copied()
+}
 
-    let (tx, ty) = *grid.keys().max().unwrap();
-    let x_cost = *costs.get(&(tx, ty, false)).unwrap();
-    let y_cost = *costs.get(&(tx, ty, true)).unwrap();
-    x_cost.min(y_cost)
+fn min_cost(grid: &Grid, min_shift: i32, max_shift: i32) -> u32 {
+    let costs = node_costs(grid, min_shift, max_shift);
+    cell_cost(&costs, *grid.keys().max().unwrap()).unwrap()
 }
 
 fn part_1(input: String) -> u32 {
@@ -65,4 +73,57 @@ fn part_2(input: String) -> u32 {
     min_cost(&parse_grid(&input), 4, 10)
 }
 
-aoc::main!();
+struct Explorer {
+    grid: Grid,
+    min_shift: i32,
+    max_shift: i32,
+    costs: HashMap<Node, u32>,
+}
+
+fn repl(input: &str) -> Repl<'_, Explorer> {
+    Repl::new(input, |input| {
+        let grid = parse_grid(input);
+        let costs = node_costs(&grid, 1, 3);
+        Explorer { grid, min_shift: 1, max_shift: 3, costs }
+    })
+    .command("rules", "<min shift> <max shift>", |explorer, args| {
+        explorer.min_shift = aoc::repl::arg(args, 0)?;
+        explorer.max_shift = aoc::repl::arg(args, 1)?;
+        explorer.costs = node_costs(&explorer.grid, explorer.min_shift, explorer.max_shift);
+        Ok(())
+    })
+    .command("query", "<x> <y>", |explorer, args| {
+        let cell = (aoc::repl::arg(args, 0)?, aoc::repl::arg(args, 1)?);
+        if !explorer.grid.contains_key(&cell) {
+            return Err(format!("{:?} is outside the grid", cell));
+        }
+        match cell_cost(&explorer.costs, cell) {
+            Some(cost) => println!("{}", cost),
+            None => println!("unreachable"),
+        }
+        Ok(())
+    })
+    .command("show", "", |explorer, _| {
+        let (tx, ty) = *explorer.grid.keys().max().unwrap();
+        for y in 0..=ty {
+            let row = (0..=tx)
+                .map(|x| match cell_cost(&explorer.costs, (x, y)) {
+                    Some(cost) => format!("{:5}", cost),
+                    None => format!("{:>5}", "-"),
+                })
+                .collect::<String>();
+            println!("{}", row);
+        }
+        println!("shifts of {} to {} per move", explorer.min_shift, explorer.max_shift);
+        Ok(())
+    })
+}
+
+fn validate(input: &str) -> Vec<Diagnostic> {
+    aoc::check_grid(input, |c| c.is_ascii_digit())
+}
+
+aoc::main! {
+    check: validate,
+    repl: repl,
+}
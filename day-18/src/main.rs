@@ -1,3 +1,25 @@
+use aoc::Diagnostic;
+
+fn parse_step(line: &str) -> Option<(char, i64)> {
+    let mut parts = line.split_ascii_whitespace();
+    let dir = parts.next()?.chars().next().filter(|d| "UDLR".contains(*d))?;
+    let dist = parts.next()?.parse().ok()?;
+    Some((dir, dist))
+}
+
+fn parse_hex_step(line: &str) -> Option<(char, i64)> {
+    let (_, hex_part) = line.rsplit_once('#')?;
+    let dist = i64::from_str_radix(hex_part.get(..5)?, 16).ok()?;
+    let dir = match hex_part.chars().nth(5)? {
+        '0' => 'R',
+        '1' => 'D',
+        '2' => 'L',
+        '3' => 'U',
+        _ => return None,
+    };
+    Some((dir, dist))
+}
+
 fn parse_waypoints(plan: &str, parse_line: impl Fn(&str) -> (char, i64)) -> Vec<(i64, i64)> {
     plan.lines()
         .map(parse_line)
@@ -38,30 +60,48 @@ fn trench_size(waypoints: &[(i64, i64)]) -> i64 {
     sum.abs() / 2
 }
 
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if input.lines().next().is_none() {
+        diagnostics.push(Diagnostic::error("empty dig plan"));
+    }
+    for (y, line) in input.lines().enumerate() {
+        if parse_step(line).is_none() {
+            let message = format!("invalid dig step '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+        if parse_hex_step(line).is_none() {
+            let message = format!("invalid colour code in '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+    }
+
+    if diagnostics.is_empty() {
+        let plans = [
+            ("dig plan", parse_waypoints(input, |l| parse_step(l).unwrap())),
+            ("colour code dig plan", parse_waypoints(input, |l| parse_hex_step(l).unwrap())),
+        ];
+        for (plan, waypoints) in plans {
+            let &(x, y) = waypoints.last().unwrap();
+            if (x, y) != (0, 0) {
+                let message = format!("{} does not close: it ends at ({}, {}) instead of the start", plan, x, y);
+                diagnostics.push(Diagnostic::error(message));
+            }
+        }
+    }
+    diagnostics
+}
+
 fn part_1(input: String) -> i64 {
-    let waypoints = parse_waypoints(&input, |line| {
-        let mut parts = line.split_ascii_whitespace();
-        let dir = parts.next().unwrap().chars().next().unwrap();
-        let dist = parts.next().unwrap().parse().unwrap();
-        (dir, dist)
-    });
+    let waypoints = parse_waypoints(&input, |line| parse_step(line).unwrap());
     trench_size(&waypoints)
 }
 
 fn part_2(input: String) -> i64 {
-    let waypoints = parse_waypoints(&input, |line| {
-        let (_, hex_part) = line.rsplit_once('#').unwrap();
-        let dist = i64::from_str_radix(&hex_part[..5], 16).unwrap();
-        let dir = match hex_part.chars().nth(5).unwrap() {
-            '0' => 'R',
-            '1' => 'D',
-            '2' => 'L',
-            '3' => 'U',
-            _ => panic!(),
-        };
-        (dir, dist)
-    });
+    let waypoints = parse_waypoints(&input, |line| parse_hex_step(line).unwrap());
     trench_size(&waypoints)
 }
 
-aoc::main!();
+aoc::main! {
+    check: validate,
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::cmp::Ordering;
+use aoc::Diagnostic;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Category {
@@ -9,64 +10,105 @@ enum Category {
 type Workflow<'s> = (Vec<(Category, Ordering, u32, &'s str)>, &'s str);
 type Part = [u32; 4];
 
-fn parse_category(category: &str) -> Category {
+fn parse_category(category: &str) -> Option<Category> {
     match category {
-        "x" => Category::X,
-        "m" => Category::M,
-        "a" => Category::A,
-        "s" => Category::S,
-        _ => panic!(),
+        "x" => Some(Category::X),
+        "m" => Some(Category::M),
+        "a" => Some(Category::A),
+        "s" => Some(Category::S),
+        _ => None,
     }
 }
 
-fn parse_ordering(ordering: &str) -> Ordering {
+fn parse_ordering(ordering: &str) -> Option<Ordering> {
     match ordering {
-        "<" => Ordering::Less,
-        ">" => Ordering::Greater,
-        _ => panic!(),
+        "<" => Some(Ordering::Less),
+        ">" => Some(Ordering::Greater),
+        _ => None,
     }
 }
 
-fn parse_workflow(workflow: &str) -> (&str, Workflow) {
-    let (name, rules) = workflow.split_once('{').unwrap();
-    let rules = rules.strip_suffix('}').unwrap();
-    let (rules, default) = rules.rsplit_once(',').unwrap();
+fn parse_workflow(workflow: &str) -> Option<(&str, Workflow<'_>)> {
+    let (name, rules) = workflow.split_once('{')?;
+    let rules = rules.strip_suffix('}')?;
+    let (rules, default) = rules.rsplit_once(',')?;
     let rules = rules.split(',');
     let rules = rules
         .map(|rule| {
-            let (predicate, target) = rule.split_once(':').unwrap();
-            let category = parse_category(&predicate[0..1]);
-            let ordering = parse_ordering(&predicate[1..2]);
-            let value = predicate[2..].parse().unwrap();
-            (category, ordering, value, target)
+            let (predicate, target) = rule.split_once(':')?;
+            let category = parse_category(predicate.get(0..1)?)?;
+            let ordering = parse_ordering(predicate.get(1..2)?)?;
+            let value = predicate.get(2..)?.parse().ok()?;
+            Some((category, ordering, value, target))
         })
-        .collect();
-    (name, (rules, default))
+        .collect::<Option<_>>()?;
+    Some((name, (rules, default)))
 }
 
-fn parse_part(part: &str) -> Part {
+fn parse_part(part: &str) -> Option<Part> {
     part
-        .strip_prefix('{')
-        .unwrap()
-        .strip_suffix('}')
-        .unwrap()
+        .strip_prefix('{')?
+        .strip_suffix('}')?
         .split(',')
-        .fold(Part::default(), |mut part, field| {
-            let (category, value) = field.split_once('=').unwrap();
-            let category = parse_category(category);
-            let value = value.parse().unwrap();
+        .try_fold(Part::default(), |mut part, field| {
+            let (category, value) = field.split_once('=')?;
+            let category = parse_category(category)?;
+            let value = value.parse().ok()?;
             part[category as usize] = value;
-            part
+            Some(part)
         })
 }
 
-fn parse_input(input: &str) -> (HashMap<&str, Workflow>, Vec<Part>) {
+fn parse_input(input: &str) -> (HashMap<&str, Workflow<'_>>, Vec<Part>) {
     let (workflows, parts) = input.split_once("\n\n").unwrap();
-    let workflows = workflows.lines().map(parse_workflow).collect();
-    let parts = parts.lines().map(parse_part).collect();
+    let workflows = workflows.lines().map(|w| parse_workflow(w).unwrap()).collect();
+    let parts = parts.lines().map(|p| parse_part(p).unwrap()).collect();
     (workflows, parts)
 }
 
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some((workflows_str, parts_str)) = input.split_once("\n\n") else {
+        diagnostics.push(Diagnostic::error("expected a blank line between workflows and parts"));
+        return diagnostics;
+    };
+
+    let mut workflows = HashMap::new();
+    for (y, line) in workflows_str.lines().enumerate() {
+        let Some((name, workflow)) = parse_workflow(line) else {
+            let message = format!("invalid workflow '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        if workflows.insert(name, (y, workflow)).is_some() {
+            let message = format!("workflow '{}' is defined more than once", name);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+    }
+    if !workflows.contains_key("in") {
+        diagnostics.push(Diagnostic::error("no 'in' workflow"));
+    }
+    for (name, (y, (rules, default))) in &workflows {
+        let targets = rules.iter().map(|&(_, _, _, t)| t).chain([*default]);
+        for target in targets {
+            if target != "A" && target != "R" && !workflows.contains_key(target) {
+                let message = format!("workflow '{}' refers to undefined workflow '{}'", name, target);
+                diagnostics.push(Diagnostic::error(message).on_line(*y));
+            }
+        }
+    }
+
+    let parts_start = workflows_str.lines().count() + 1;
+    for (y, line) in parts_str.lines().enumerate() {
+        if parse_part(line).is_none() {
+            let message = format!("invalid part '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(parts_start + y));
+        }
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
 fn part_1(input: String) -> u32 {
     let (workflows, parts) = parse_input(&input);
 
@@ -123,4 +165,6 @@ fn part_2(input: String) -> u64 {
     sum
 }
 
-aoc::main!();
+aoc::main! {
+    check: validate,
+}
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, VecDeque};
+use aoc::{Diagnostic, Repl};
 
 enum Module<'s> {
     FlipFlop {
@@ -11,7 +12,7 @@ enum Module<'s> {
     },
 }
 
-fn parse_modules(modules_str: &str) -> (Vec<&str>, HashMap<&str, Module>) {
+fn parse_modules(modules_str: &str) -> (Vec<&str>, HashMap<&str, Module<'_>>) {
     let mut broadcaster = Vec::new();
     let mut modules = HashMap::new();
     for module in modules_str.lines() {
@@ -53,6 +54,48 @@ fn parse_modules(modules_str: &str) -> (Vec<&str>, HashMap<&str, Module>) {
     (broadcaster, modules)
 }
 
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut modules = HashMap::new();
+    for (y, module) in input.lines().enumerate() {
+        let Some((name, outputs)) = module.split_once(" -> ") else {
+            let message = format!("invalid module '{}'", module);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        let name = match name {
+            "broadcaster" => name,
+            _ => match name.strip_prefix('%').or_else(|| name.strip_prefix('&')) {
+                Some(name) => name,
+                None => {
+                    let message = format!("module '{}' has no type prefix ('%' or '&')", name);
+                    diagnostics.push(Diagnostic::error(message).on_line(y));
+                    continue;
+                }
+            }
+        };
+        let outputs = outputs.split(", ").collect::<Vec<_>>();
+        if modules.insert(name, (y, outputs)).is_some() {
+            let message = format!("module '{}' is defined more than once", name);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+    }
+
+    if !modules.contains_key("broadcaster") {
+        diagnostics.push(Diagnostic::error("no broadcaster module"));
+    }
+    for (name, (y, outputs)) in &modules {
+        for output in outputs {
+            if !modules.contains_key(output) {
+                let message = format!("module '{}' outputs to '{}', which is never defined", name, output);
+                diagnostics.push(Diagnostic::warning(message).on_line(*y));
+            }
+        }
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
 fn broadcast<'s>(
     broadcaster: &[&'s str],
     modules: &mut HashMap<&'s str, Module<'s>>,
@@ -82,7 +125,7 @@ fn part_1(input: String) -> u32 {
     let (broadcaster, mut modules) = parse_modules(&input);
     let mut low_pulses = 0;
     let mut high_pulses = 0;
-    for _ in 0..1000 {
+    for _ in 0..params().presses {
         low_pulses += 1;
         broadcast(&broadcaster, &mut modules, |_, _, high| {
             match high {
@@ -134,4 +177,85 @@ fn part_2(input: String) -> u64 {
     counter_sizes.into_values().reduce(lcm).unwrap()
 }
 
-aoc::main!();
+struct Network<'s> {
+    broadcaster: Vec<&'s str>,
+    modules: HashMap<&'s str, Module<'s>>,
+    presses: u64,
+}
+
+fn pulse_name(high: bool) -> &'static str {
+    match high {
+        false => "low",
+        true => "high",
+    }
+}
+
+fn describe_module(name: &str, module: &Module) -> String {
+    match module {
+        Module::FlipFlop { state, outputs } => {
+            let state = if *state { "on" } else { "off" };
+            format!("%{} [{}] -> {}", name, state, outputs.join(", "))
+        }
+        Module::Conjunction { state, outputs } => {
+            let mut inputs = state.iter()
+                .map(|(input, &high)| format!("{}={}", input, pulse_name(high)))
+                .collect::<Vec<_>>();
+            inputs.sort_unstable();
+            format!("&{} [{}] -> {}", name, inputs.join(" "), outputs.join(", "))
+        }
+    }
+}
+
+fn repl(input: &str) -> Repl<'_, Network<'_>> {
+    Repl::new(input, |input| {
+        let (broadcaster, modules) = parse_modules(input);
+        Network { broadcaster, modules, presses: 0 }
+    })
+    .command("step", "[presses]", |network, args| {
+        let presses = aoc::repl::arg_or(args, 0, 1)?;
+        let mut low_pulses = 0;
+        let mut high_pulses = 0;
+        for _ in 0..presses {
+            network.presses += 1;
+            low_pulses += 1;
+            if presses == 1 {
+                println!("button -low-> broadcaster");
+            }
+            broadcast(&network.broadcaster, &mut network.modules, |source, target, high| {
+                match high {
+                    false => low_pulses += 1,
+                    true => high_pulses += 1,
+                }
+                if presses == 1 {
+                    println!("{} -{}-> {}", source, pulse_name(high), target);
+                }
+            });
+        }
+        println!("{} presses so far; {} low and {} high pulses sent", network.presses, low_pulses, high_pulses);
+        Ok(())
+    })
+    .command("show", "", |network, _| {
+        let mut names = network.modules.keys().collect::<Vec<_>>();
+        names.sort_unstable();
+        println!("broadcaster -> {}", network.broadcaster.join(", "));
+        for name in names {
+            println!("{}", describe_module(name, &network.modules[name]));
+        }
+        println!("{} presses so far", network.presses);
+        Ok(())
+    })
+    .command("query", "<module>", |network, args| {
+        let name = args.first().ok_or("missing module name")?;
+        let module = network.modules.get(name).ok_or_else(|| format!("no module '{}'", name))?;
+        println!("{}", describe_module(name, module));
+        Ok(())
+    })
+}
+
+aoc::main! {
+    params {
+        presses: u32 = 1000,
+    }
+    check: validate,
+    repl: repl,
+}
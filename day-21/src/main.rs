@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use aoc::Diagnostic;
 
 type Cell = (i32, i32);
 
@@ -47,9 +48,40 @@ fn parse_input(input: &str) -> (Grid<char>, Cell) {
     (Grid { grid, width, height }, start)
 }
 
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut starts = Vec::new();
+    let width = input.lines().next().map_or(0, str::len);
+    for (y, line) in input.lines().enumerate() {
+        if line.len() != width {
+            let message = format!("row has width {}, expected {}", line.len(), width);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' | '#' => {}
+                'S' => starts.push((x, y)),
+                _ => diagnostics.push(Diagnostic::error(format!("unknown tile '{}'", c)).at(y, x)),
+            }
+        }
+    }
+    if width == 0 {
+        diagnostics.push(Diagnostic::error("empty grid"));
+    }
+    match starts.as_slice() {
+        [] => diagnostics.push(Diagnostic::error("no start tile 'S'")),
+        [_] => {}
+        _ => for &(x, y) in &starts {
+            let message = format!("multiple start tiles ({} in total)", starts.len());
+            diagnostics.push(Diagnostic::error(message).at(y, x));
+        }
+    }
+    diagnostics
+}
+
 fn part_1(input: String) -> usize {
     let (grid, start) = parse_input(&input);
-    (0..64)
+    (0..params().part_1_steps)
         .fold(HashSet::from([start]), |current, _| {
             current.into_iter()
                 .flat_map(|(x, y)| [
@@ -78,7 +110,7 @@ fn distance_map(grid: &Grid<char>, start: Cell) -> [[Grid<Option<u32>>; 3]; 3] {
         let (dx, gx) = (x.div_euclid(grid.width), x.rem_euclid(grid.width));
         let (dy, gy) = (y.div_euclid(grid.height), y.rem_euclid(grid.height));
         
-        if dx < -1 || dx > 1 || dy < -1 || dy > 1 {
+        if !(-1..=1).contains(&dx) || !(-1..=1).contains(&dy) {
             continue;
         }
 
@@ -101,9 +133,8 @@ fn distance_map(grid: &Grid<char>, start: Cell) -> [[Grid<Option<u32>>; 3]; 3] {
     distances
 }
 
-const STEPS: u32 = 26501365;
-
 fn part_2(input: String) -> u64 {
+    let steps = params().part_2_steps;
     let (grid, (sx, sy)) = parse_input(&input);
     let map = distance_map(&grid, (sx, sy));
 
@@ -121,7 +152,7 @@ fn part_2(input: String) -> u64 {
                 for x in 0..grid.width {
                     if let Some(distance) = *map.get(x, y).unwrap() {
                         let distance = distance + grid_distance as u32;
-                        if distance <= STEPS && (STEPS - distance) % 2 == 0 {
+                        if distance <= steps && (steps - distance).is_multiple_of(2) {
                             valid += 1;
                         }
                     }
@@ -131,10 +162,10 @@ fn part_2(input: String) -> u64 {
         })
     };
 
-    let gsx = (sx - STEPS as i32) / grid.width - 1;
-    let gex = (sx + STEPS as i32) / grid.width + 1;
-    let gsy = (sy - STEPS as i32) / grid.height - 1;
-    let gey = (sy + STEPS as i32) / grid.height + 1;
+    let gsx = (sx - steps as i32) / grid.width - 1;
+    let gex = (sx + steps as i32) / grid.width + 1;
+    let gsy = (sy - steps as i32) / grid.height - 1;
+    let gey = (sy + steps as i32) / grid.height + 1;
 
     let mut valid = 0;
     for x in gsx..gex {
@@ -204,4 +235,10 @@ fn part_2(input: String) -> u64 {
     valid
 }
 
-aoc::main!();
+aoc::main! {
+    params {
+        part_1_steps: u32 = 64,
+        part_2_steps: u32 = 26501365,
+    }
+    check: validate,
+}
@@ -1,4 +1,5 @@
 use std::ops::Range;
+use aoc::Diagnostic;
 
 #[derive(Debug, Clone)]
 struct Brick {
@@ -7,20 +8,24 @@ struct Brick {
     z: Range<i32>,
 }
 
+fn parse_corner(corner: &str) -> Option<[i32; 3]> {
+    let mut nums = corner.split(',').map(|n| n.parse().ok());
+    let corner = [nums.next()??, nums.next()??, nums.next()??];
+    nums.next().is_none().then_some(corner)
+}
+
+fn parse_brick(brick: &str) -> Option<Brick> {
+    let (start, end) = brick.split_once('~')?;
+    let (start, end) = (parse_corner(start)?, parse_corner(end)?);
+    Some(Brick {
+        x: start[0]..end[0] + 1,
+        y: start[1]..end[1] + 1,
+        z: start[2]..end[2] + 1,
+    })
+}
+
 fn parse_bricks(bricks: &str) -> Vec<Brick> {
-    bricks.lines()
-        .map(|brick| {
-            let (start, end) = brick.split_once('~').unwrap();
-            let start = start.split(',').map(|n| n.parse().unwrap());
-            let end = end.split(',').map(|n| n.parse::<i32>().unwrap());
-            let mut ranges = start.zip(end).map(|(s, e)| s..e + 1);
-            Brick {
-                x: ranges.next().unwrap(),
-                y: ranges.next().unwrap(),
-                z: ranges.next().unwrap(),
-            }
-        })
-        .collect()
+    bricks.lines().map(|brick| parse_brick(brick).unwrap()).collect()
 }
 
 fn collides(a: &Brick, b: &Brick) -> bool {
@@ -73,4 +78,24 @@ fn part_2(input: String) -> u32 {
         .sum()
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (y, line) in input.lines().enumerate() {
+        let Some(brick) = parse_brick(line) else {
+            let message = format!("expected 'x,y,z~x,y,z', got '{}'", line);
+            diagnostics.push(Diagnostic::error(message).on_line(y));
+            continue;
+        };
+        if brick.x.is_empty() || brick.y.is_empty() || brick.z.is_empty() {
+            diagnostics.push(Diagnostic::error("brick ends before it starts").on_line(y));
+        }
+        if brick.z.start < 1 {
+            diagnostics.push(Diagnostic::error("brick is below the ground at z = 1").on_line(y));
+        }
+    }
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+}
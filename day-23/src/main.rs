@@ -1,4 +1,5 @@
 use std::collections::{HashSet, HashMap};
+use aoc::Diagnostic;
 
 type Cell = (i32, i32);
 type Grid = HashMap<Cell, char>;
@@ -89,4 +90,14 @@ fn part_2(input: String) -> u32 {
     longest_path(&graph, target, &mut HashSet::new(), 0, (1, 0))
 }
 
-aoc::main!();
+fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = aoc::check_grid(input, |c| "#.<>^v".contains(c));
+    if diagnostics.is_empty() && input.lines().next().and_then(|r| r.chars().nth(1)) != Some('.') {
+        diagnostics.push(Diagnostic::error("the start at (1, 0) isn't a path tile").at(0, 1));
+    }
+    diagnostics
+}
+
+aoc::main! {
+    check: validate,
+}